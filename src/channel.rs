@@ -0,0 +1,146 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::{push::Push, Client, Result};
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct CreateRequest {
+    /// Globally unique identifier for this channel, chosen by the channel creator
+    pub tag: Option<String>,
+
+    /// Name of the channel
+    pub name: Option<String>,
+
+    /// Description of the channel
+    pub description: Option<String>,
+
+    /// Image to display for the channel
+    pub image_url: Option<String>,
+
+    /// Website for the channel
+    pub website_url: Option<String>,
+
+    /// URL for RSS feed. If this is set, the RSS feed will be used to automatically create posts for this channel
+    pub feed_url: Option<String>,
+
+    /// Filters to use when a feed_url is set, only posts matching these filters will be sent out on the channel.
+    pub feed_filters: Vec<CreateFilter>,
+
+    /// If this is set to true, a subscription will be created as soon as the channel is created.
+    pub subscribe: Option<bool>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct CreateFilter {
+    /// Field to match filter against, only "title" is currently supported
+    pub field: Option<String>,
+
+    /// Operation for filter to match value against the chosen field, only "contains" is currently supported
+    pub operator: Option<String>,
+
+    /// Value to compare to the field using the chosen operator
+    pub value: Option<String>,
+
+    /// Invert the result of this filter
+    pub not: Option<bool>,
+
+    /// If true, match without regards to lowercase/uppercase
+    pub ignore_case: Option<bool>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct InfoRequest {
+    /// Tag of the channel to get information for
+    pub tag: Option<String>,
+
+    /// Don't show recent pushes, defaults to false
+    pub no_recent_pushes: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Channel {
+    /// Unique identifier for this object
+    pub iden: String,
+
+    /// false if the item has been deleted
+    pub active: Option<bool>,
+
+    /// Creation time in floating point seconds (unix timestamp)
+    pub created: Option<f64>,
+
+    /// Last modified time in floating point seconds (unix timestamp)
+    pub modified: Option<f64>,
+
+    /// Globally unique identifier for this channel, chosen by the channel creator
+    pub tag: Option<String>,
+
+    /// Name of the channel
+    pub name: Option<String>,
+
+    /// Description of the channel
+    pub description: Option<String>,
+
+    /// Image to display for the channel
+    pub image_url: Option<String>,
+
+    /// Website for the channel
+    pub website_url: Option<String>,
+
+    /// URL for RSS feed used to automatically create posts for this channel
+    pub feed_url: Option<String>,
+
+    /// Fields not known to this client.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChannelInfo {
+    /// Unique identifier for the channel
+    pub iden: String,
+
+    /// Globally unique identifier for this channel, chosen by the channel creator
+    pub tag: Option<String>,
+
+    /// Name of the channel
+    pub name: Option<String>,
+
+    /// Description of the channel
+    pub description: Option<String>,
+
+    /// Image to display for the channel
+    pub image_url: Option<String>,
+
+    /// Number of users subscribed to the channel
+    pub subscriber_count: Option<i64>,
+
+    /// Website for the channel
+    pub website_url: Option<String>,
+
+    /// Recent pushes sent to the channel, unless no_recent_pushes was set
+    pub recent_pushes: Option<Vec<Push>>,
+
+    /// Fields not known to this client.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+pub struct Channels<'a> {
+    client: &'a Client,
+}
+
+impl<'a> Channels<'a> {
+    pub(crate) fn new(client: &'a Client) -> Channels<'a> {
+        Channels { client }
+    }
+
+    /// Create a channel.
+    pub fn create(&self, request: &CreateRequest) -> Result<Channel> {
+        self.client.post("/channels", request)
+    }
+
+    /// Get information about a channel.
+    pub fn info(&self, request: &InfoRequest) -> Result<ChannelInfo> {
+        self.client.get("/channel-info", request)
+    }
+}
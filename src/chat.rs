@@ -0,0 +1,132 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::{Client, Page, Pagination, Paginator, Result};
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct CreateRequest {
+    /// Email of person to create chat with (does not have to be a Pushbullet user)
+    /// Example: "carmack@idsoftware.com"
+    pub email: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct UpdateRequest {
+    /// true to mute the grant, false to unmute it
+    pub muted: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Chat {
+    /// Unique identifier for this object
+    pub iden: String,
+
+    /// false if the item has been deleted
+    pub active: bool,
+
+    /// Creation time in floating point seconds (unix timestamp)
+    pub created: f64,
+
+    /// Last modified time in floating point seconds (unix timestamp)
+    pub modified: f64,
+
+    /// If true, notifications from this chat will not be shown
+    pub muted: Option<bool>,
+
+    /// The user or email that the chat is with
+    pub with: Option<ChatWith>,
+
+    /// Fields not known to this client.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatWith {
+    /// If this is a user, the iden of that user
+    pub iden: Option<String>,
+
+    /// Is this person a "user" or just an "email" address?
+    #[serde(rename = "type")]
+    pub t: Option<String>,
+
+    /// Name of the person
+    pub name: Option<String>,
+
+    /// Email address of the person
+    pub email: Option<String>,
+
+    /// Canonical email address of the person
+    pub email_normalized: Option<String>,
+
+    /// Image to display for the person
+    pub image_url: Option<String>,
+
+    /// Fields not known to this client.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListResponse {
+    pub chats: Vec<Chat>,
+
+    /// Cursor for the next page of results, absent on the last page.
+    pub cursor: Option<String>,
+
+    /// Fields not known to this client.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl Page for ListResponse {
+    type Item = Chat;
+
+    fn cursor(&self) -> Option<&str> {
+        self.cursor.as_deref()
+    }
+
+    fn into_items(self) -> Vec<Chat> {
+        self.chats
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct Chats<'a> {
+    client: &'a Client,
+}
+
+impl<'a> Chats<'a> {
+    pub(crate) fn new(client: &'a Client) -> Chats<'a> {
+        Chats { client }
+    }
+
+    /// Get a list of chats belonging to the current user.
+    pub fn list(&self, pagination: &Pagination) -> Result<ListResponse> {
+        self.client.get("/chats", pagination)
+    }
+
+    /// Iterate over every chat, following the cursor from page to page.
+    pub fn iter(&self, mut pagination: Pagination) -> Paginator<'a, ListResponse> {
+        let chats = *self;
+        Paginator::new(pagination.cursor.take(), move |cursor| {
+            pagination.cursor = cursor;
+            chats.list(&pagination)
+        })
+    }
+
+    /// Create a chat with another user or email address if one does not already exist.
+    pub fn create(&self, request: &CreateRequest) -> Result<Chat> {
+        self.client.post("/chats", request)
+    }
+
+    /// Update existing chat object.
+    pub fn update(&self, iden: &str, request: &UpdateRequest) -> Result<Chat> {
+        self.client.post(&format!("/chats/{}", iden), request)
+    }
+
+    /// Delete a chat object.
+    pub fn delete(&self, iden: &str) -> Result<()> {
+        self.client.delete(&format!("/chats/{}", iden))
+    }
+}
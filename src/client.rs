@@ -0,0 +1,156 @@
+use std::time::Duration;
+
+use reqwest::blocking::RequestBuilder;
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{
+    channel::Channels, chat::Chats, device::Devices, ephemeral::Ephemerals, error::ErrorResponse,
+    push::Pushes, stream::Stream, subscription::Subscriptions, text::Texts, user::Users, PbError,
+    Result,
+};
+
+/// Base url of the Pushbullet HTTP API.
+pub const API_URL: &str = "https://api.pushbullet.com/v2";
+
+/// Client for the Pushbullet HTTP API, authenticated with an access token.
+pub struct Client {
+    pub(crate) http: reqwest::blocking::Client,
+    access_token: String,
+    api_url: String,
+}
+
+impl Client {
+    pub fn new(access_token: &str) -> Client {
+        Client {
+            http: reqwest::blocking::Client::new(),
+            access_token: access_token.to_owned(),
+            api_url: API_URL.to_owned(),
+        }
+    }
+
+    /// Send requests to another base url than https://api.pushbullet.com/v2, such as a proxy or a mock server.
+    pub fn with_api_url(mut self, api_url: &str) -> Client {
+        self.api_url = api_url.trim_end_matches('/').to_owned();
+        self
+    }
+
+    /// Limit the time of whole requests and of establishing connections, instead of the 30 seconds request timeout of reqwest.
+    pub fn with_timeouts(
+        mut self,
+        timeout: Option<Duration>,
+        connect_timeout: Option<Duration>,
+    ) -> Result<Client> {
+        let mut builder = reqwest::blocking::Client::builder();
+        if let Some(timeout) = timeout {
+            builder = builder.timeout(timeout);
+        }
+        if let Some(connect_timeout) = connect_timeout {
+            builder = builder.connect_timeout(connect_timeout);
+        }
+        self.http = builder.build()?;
+        Ok(self)
+    }
+
+    /// Push history and sending pushes.
+    pub fn pushes(&self) -> Pushes<'_> {
+        Pushes::new(self)
+    }
+
+    /// Devices belonging to the current user.
+    pub fn devices(&self) -> Devices<'_> {
+        Devices::new(self)
+    }
+
+    /// Chats with other users.
+    pub fn chats(&self) -> Chats<'_> {
+        Chats::new(self)
+    }
+
+    /// Channels owned by the current user and channel information.
+    pub fn channels(&self) -> Channels<'_> {
+        Channels::new(self)
+    }
+
+    /// Channel subscriptions of the current user.
+    pub fn subscriptions(&self) -> Subscriptions<'_> {
+        Subscriptions::new(self)
+    }
+
+    /// Text messages (SMS/MMS) sent through a phone.
+    pub fn texts(&self) -> Texts<'_> {
+        Texts::new(self)
+    }
+
+    /// Ephemeral pushes, such as universal copy/paste clips, sent to the devices without being stored.
+    pub fn ephemerals(&self) -> Ephemerals<'_> {
+        Ephemerals::new(self)
+    }
+
+    /// The current user.
+    pub fn users(&self) -> Users<'_> {
+        Users::new(self)
+    }
+
+    /// Connection to the realtime event stream for the same account.
+    pub fn stream(&self) -> Stream {
+        Stream::new(&self.access_token)
+    }
+
+    pub fn access_token(&self) -> &str {
+        &self.access_token
+    }
+
+    pub fn api_url(&self) -> &str {
+        &self.api_url
+    }
+
+    pub(crate) fn get<Q, T>(&self, path: &str, query: &Q) -> Result<T>
+    where
+        Q: Serialize + ?Sized,
+        T: DeserializeOwned,
+    {
+        self.send(self.http.get(self.url(path)).query(query))
+    }
+
+    pub(crate) fn post<B, T>(&self, path: &str, body: &B) -> Result<T>
+    where
+        B: Serialize + ?Sized,
+        T: DeserializeOwned,
+    {
+        self.send(self.http.post(self.url(path)).json(body))
+    }
+
+    pub(crate) fn delete(&self, path: &str) -> Result<()> {
+        self.execute(self.http.delete(self.url(path)))?;
+        Ok(())
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", self.api_url, path)
+    }
+
+    fn authorize(&self, request_builder: RequestBuilder) -> RequestBuilder {
+        request_builder.header("Access-Token", &self.access_token)
+    }
+
+    fn send<T: DeserializeOwned>(&self, request_builder: RequestBuilder) -> Result<T> {
+        let text = self.execute(request_builder)?;
+        Ok(serde_json::from_str(&text)?)
+    }
+
+    /// Send the request and return the response body, failing on any non-success status.
+    fn execute(&self, request_builder: RequestBuilder) -> Result<String> {
+        let response = self.authorize(request_builder).send()?;
+        let status = response.status();
+        let text = response.text()?;
+        if !status.is_success() {
+            return Err(PbError::Api {
+                status: status.as_u16(),
+                error: serde_json::from_str::<ErrorResponse>(&text)
+                    .ok()
+                    .map(|response| response.error),
+            });
+        }
+        Ok(text)
+    }
+}
@@ -0,0 +1,154 @@
+use std::{fs::File, io::Write};
+
+use clap::Args;
+use pushbullet_rust::{
+    push::{ListRequest, UpdateRequest},
+    Client, PbError, Result,
+};
+use serde_json::{json, Value};
+
+use super::{
+    guard::{find_all, GuardArgs},
+    journal::Kind,
+    search::SearchArgs,
+};
+
+/// Pushes changed by pb push dismiss and pb push delete, given by iden or by filters.
+#[derive(Args, Clone)]
+pub struct BulkArgs {
+    /// Idens of the pushes. Without any, the pushes matching the filters are changed.
+    pub idens: Vec<String>,
+
+    #[command(flatten)]
+    pub filter: SearchArgs,
+}
+
+impl BulkArgs {
+    /// The pushes given by iden, or the ones matching the filters. all allows selecting every push without a filter.
+    fn select(&self, client: &Client, all: bool) -> Result<Vec<Value>> {
+        if !self.idens.is_empty() && !self.filter.is_empty() {
+            return Err(PbError::Config(String::from(
+                "give either push idens or filters, not both",
+            )));
+        }
+        if self.idens.is_empty() && self.filter.is_empty() && !all {
+            return Err(PbError::Config(String::from(
+                "no pushes selected, give push idens or filters such as --older-than 30d --type link",
+            )));
+        }
+        if !self.idens.is_empty() {
+            let request = ListRequest {
+                active: Some(true),
+                limit: Some(500),
+                ..Default::default()
+            };
+            return find_all(Kind::Push, client.pushes().iter(request), &self.idens);
+        }
+        self.filter
+            .find(client)?
+            .into_iter()
+            .map(|push| Ok(serde_json::to_value(push)?))
+            .collect()
+    }
+}
+
+#[derive(Args)]
+pub struct DismissArgs {
+    /// Dismiss every push not dismissed yet, without needing a filter.
+    #[arg(long)]
+    pub all_undismissed: bool,
+
+    #[command(flatten)]
+    pub bulk: BulkArgs,
+
+    /// Print the pushes that would be dismissed without changing anything.
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+impl DismissArgs {
+    /// Dismiss every selected push, going on after failures and returning the last one.
+    pub fn request(&self, client: &Client) -> Result<Value> {
+        let mut bulk = self.bulk.clone();
+        // Dismissing a dismissed push changes nothing. Added only once pushes are selected, so that a bare dismiss still asks for a selection.
+        if bulk.idens.is_empty() && (self.all_undismissed || !bulk.filter.is_empty()) {
+            bulk.filter.dismissed = Some(false);
+        }
+        let pushes = bulk.select(client, self.all_undismissed)?;
+        if self.dry_run {
+            eprintln!("Would dismiss {} pushes", pushes.len());
+            return Ok(json!({ "pushes": pushes }));
+        }
+
+        let total = pushes.len();
+        let mut dismissed = vec![];
+        let mut last_error = None;
+        for push in pushes {
+            let iden = push["iden"].as_str().unwrap_or_default();
+            let request = UpdateRequest {
+                dismissed: Some(true),
+            };
+            match client.pushes().update(iden, &request) {
+                Ok(push) => dismissed.push(push),
+                Err(e) => {
+                    eprintln!("Failed to dismiss {iden}: {e}");
+                    last_error = Some(e);
+                }
+            }
+        }
+        if let Some(e) = last_error {
+            eprintln!(
+                "{} of {total} pushes failed, {} dismissed",
+                total - dismissed.len(),
+                dismissed.len()
+            );
+            return Err(e);
+        }
+        eprintln!("Dismissed {} pushes", dismissed.len());
+        Ok(json!({ "pushes": dismissed }))
+    }
+}
+
+#[derive(Args)]
+pub struct DeleteArgs {
+    #[command(flatten)]
+    pub bulk: BulkArgs,
+
+    #[command(flatten)]
+    pub guard: GuardArgs,
+}
+
+impl DeleteArgs {
+    pub fn request(&self, client: &Client) -> Result<Value> {
+        let pushes = self.bulk.select(client, false)?;
+        self.guard.delete(client, Kind::Push, pushes, |iden| {
+            client.pushes().delete(iden)
+        })
+    }
+}
+
+#[derive(Args)]
+pub struct ExportArgs {
+    #[command(flatten)]
+    pub filter: SearchArgs,
+
+    /// Write the pushes to this file as JSON lines, one push per line, instead of printing them.
+    #[arg(long, value_name = "FILE")]
+    pub to: Option<String>,
+}
+
+impl ExportArgs {
+    pub fn request(&self, client: &Client) -> Result<Value> {
+        let pushes = self.filter.find(client)?;
+        let Some(to) = &self.to else {
+            return Ok(json!({ "pushes": pushes }));
+        };
+        let io_error = |e: std::io::Error| PbError::Io(format!("{to}: {e}"));
+        let mut file = File::create(to).map_err(io_error)?;
+        for push in &pushes {
+            writeln!(file, "{}", serde_json::to_string(push)?).map_err(io_error)?;
+        }
+        eprintln!("Exported {} pushes to {to}", pushes.len());
+        Ok(json!({}))
+    }
+}
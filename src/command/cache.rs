@@ -0,0 +1,189 @@
+use std::{
+    collections::HashMap,
+    env, fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+use clap::Subcommand;
+use pushbullet_rust::{push::ListRequest, Client, Page, Pagination, Paginator, PbError, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+
+use super::config::{check_profile, write_private};
+
+/// Objects kept in the cache, each in a <key>.json file.
+pub const KEYS: [&str; 4] = ["pushes", "devices", "chats", "subscriptions"];
+
+#[derive(Subcommand)]
+pub enum CacheCommands {
+    /// Fetch the objects modified since the last sync into the cache, every object the first time.
+    Sync,
+
+    /// Print the number of cached objects and the time of the newest change seen.
+    Status,
+
+    /// Remove the cache of the selected profile.
+    Clear,
+}
+
+impl CacheCommands {
+    /// Run the command, client being None with --offline.
+    pub fn run(&self, client: Option<&Client>, cache: &Cache) -> Result<Value> {
+        match self {
+            CacheCommands::Sync => match client {
+                Some(client) => cache.sync(client),
+                None => Err(PbError::Config(String::from(
+                    "cache sync needs the API and does not work with --offline",
+                ))),
+            },
+            CacheCommands::Status => {
+                let mut status = Map::new();
+                for key in KEYS {
+                    let store = cache.load(key)?;
+                    status.insert(
+                        key.to_owned(),
+                        json!({ "items": store.items.len(), "modified": store.modified }),
+                    );
+                }
+                Ok(Value::Object(status))
+            }
+            CacheCommands::Clear => {
+                match fs::remove_dir_all(&cache.dir) {
+                    Err(e) if e.kind() != ErrorKind::NotFound => {
+                        return Err(PbError::Io(format!("{}: {e}", cache.dir.display())))
+                    }
+                    _ => (),
+                }
+                Ok(json!({}))
+            }
+        }
+    }
+}
+
+/// Objects of one key and the newest modified timestamp seen, from which the next sync starts.
+#[derive(Default, Serialize, Deserialize)]
+struct Store {
+    modified: f64,
+    synced: bool,
+    items: Vec<Value>,
+}
+
+/// Local copy of the pushes, devices, chats and subscriptions of a profile, under ~/.cache/pbr/<profile>.
+pub struct Cache {
+    dir: PathBuf,
+}
+
+impl Cache {
+    pub fn open(profile: &str) -> Result<Cache> {
+        let home = env::var("HOME").map_err(|e| PbError::Config(format!("HOME: {e}")))?;
+        Ok(Cache {
+            dir: Path::new(&home)
+                .join(".cache")
+                .join("pbr")
+                .join(check_profile(profile)?),
+        })
+    }
+
+    /// Bring every key up to date, returning how many objects changed.
+    pub fn sync(&self, client: &Client) -> Result<Value> {
+        let pushes = client.pushes();
+        let (devices, chats, subscriptions) =
+            (client.devices(), client.chats(), client.subscriptions());
+        let pagination = |modified_after| Pagination {
+            modified_after,
+            cursor: None,
+            limit: Some(500),
+        };
+        Ok(json!({
+            "pushes": self.sync_key("pushes", |modified_after| {
+                pushes.iter(ListRequest {
+                    // Deleted pushes only matter to remove them from an earlier sync.
+                    active: modified_after.is_none().then_some(true),
+                    modified_after,
+                    cursor: None,
+                    limit: Some(500),
+                })
+            })?,
+            "devices": self.sync_key("devices", |modified_after| devices.iter(pagination(modified_after)))?,
+            "chats": self.sync_key("chats", |modified_after| chats.iter(pagination(modified_after)))?,
+            "subscriptions": self.sync_key("subscriptions", |modified_after| {
+                subscriptions.iter(pagination(modified_after))
+            })?,
+        }))
+    }
+
+    /// Merge the objects modified since the last sync of key, dropping the ones no longer active.
+    fn sync_key<'a, P, F>(&self, key: &str, list: F) -> Result<Value>
+    where
+        P: Page,
+        P::Item: Serialize,
+        F: FnOnce(Option<String>) -> Paginator<'a, P>,
+    {
+        let mut store = self.load(key)?;
+        let modified_after = store.synced.then(|| store.modified.to_string());
+        let mut items: HashMap<String, Value> = store
+            .items
+            .drain(..)
+            .filter_map(|item| Some((item["iden"].as_str()?.to_owned(), item)))
+            .collect();
+
+        let (mut updated, mut deleted) = (0, 0);
+        for item in list(modified_after) {
+            let item = serde_json::to_value(item?)?;
+            let Some(iden) = item["iden"].as_str().map(str::to_owned) else {
+                continue;
+            };
+            store.modified = store.modified.max(item["modified"].as_f64().unwrap_or(0.0));
+            if item["active"] == json!(false) {
+                deleted += items.remove(&iden).is_some() as usize;
+            } else {
+                updated += 1;
+                items.insert(iden, item);
+            }
+        }
+
+        store.items = items.into_values().collect();
+        // Newest first, as the API lists them.
+        store.items.sort_by(|a, b| {
+            let modified = |item: &Value| item["modified"].as_f64().unwrap_or(0.0);
+            modified(b).total_cmp(&modified(a))
+        });
+        store.synced = true;
+        self.save(key, &store)?;
+        Ok(json!({ "items": store.items.len(), "updated": updated, "deleted": deleted }))
+    }
+
+    /// Cached objects of key, newest first. Fails when key was never synced.
+    pub fn items(&self, key: &str) -> Result<Vec<Value>> {
+        let store = self.load(key)?;
+        if !store.synced {
+            return Err(PbError::Io(format!(
+                "no {key} in the cache, run pb cache sync first"
+            )));
+        }
+        Ok(store.items)
+    }
+
+    /// File of key in the cache directory.
+    pub fn path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{key}.json"))
+    }
+
+    fn load(&self, key: &str) -> Result<Store> {
+        let path = self.path(key);
+        let cache_error = |e: String| PbError::Io(format!("{}: {e}", path.display()));
+        match fs::read(&path) {
+            Ok(bytes) => serde_json::from_slice(&bytes).map_err(|e| cache_error(e.to_string())),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(Store::default()),
+            Err(e) => Err(cache_error(e.to_string())),
+        }
+    }
+
+    fn save(&self, key: &str, store: &Store) -> Result<()> {
+        let path = self.path(key);
+        // Pushes are as private as the access token.
+        write_private(&path, &serde_json::to_vec(store)?)
+            .map_err(|e| PbError::Io(format!("{}: {e}", path.display())))
+    }
+}
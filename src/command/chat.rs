@@ -1,10 +1,16 @@
-use std::error::Error;
-
 use clap::Subcommand;
-use reqwest::blocking::RequestBuilder;
-use serde::{Deserialize, Serialize};
+use pushbullet_rust::{
+    chat::{CreateRequest, UpdateRequest},
+    Client, Pagination, Result,
+};
+use serde_json::Value;
 
-use super::{PaginationArgs, Request};
+use super::{
+    collect_pages,
+    guard::{find, GuardArgs},
+    journal::Kind,
+    PaginationArgs, Request,
+};
 
 #[derive(Subcommand)]
 pub enum ChatCommands {
@@ -38,47 +44,31 @@ pub enum ChatCommands {
     Delete {
         /// Unique identifier for this object
         iden: String,
-    },
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct CreateRequest {
-    /// Email of person to create chat with (does not have to be a Pushbullet user)
-    /// Example: "carmack@idsoftware.com"
-    email: Option<String>,
-}
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct UpdateRequest {
-    /// true to mute the grant, false to unmute it
-    muted: Option<bool>,
+        #[command(flatten)]
+        guard: GuardArgs,
+    },
 }
 
 impl Request for ChatCommands {
-    fn build_request(&self, access_token: &str) -> Result<RequestBuilder, Box<dyn Error>> {
+    fn request(&self, client: &Client) -> Result<Value> {
         match self {
-            ChatCommands::List(args) => {
-                let request_builder = reqwest::blocking::Client::new()
-                    .get("https://api.pushbullet.com/v2/chats")
-                    .query(&args.to_query());
-                Ok(request_builder)
-            }
+            ChatCommands::List(args) if args.all => collect_pages(
+                "chats",
+                client.chats().iter(args.to_request()),
+                args.max_items,
+            ),
+            ChatCommands::List(args) => Ok(serde_json::to_value(
+                client.chats().list(&args.to_request())?,
+            )?),
             ChatCommands::Create { email, data_binary } => {
                 let request = match data_binary {
-                    Some(data_binary) => match serde_json::from_str(&data_binary) {
-                        Ok(request) => request,
-                        Err(error) => {
-                            return Err(Box::new(error));
-                        }
-                    },
+                    Some(data_binary) => serde_json::from_str(data_binary)?,
                     None => CreateRequest {
                         email: email.clone(),
                     },
                 };
-                let request_builder = reqwest::blocking::Client::new()
-                    .post("https://api.pushbullet.com/v2/chats")
-                    .json(&request);
-                Ok(request_builder)
+                Ok(serde_json::to_value(client.chats().create(&request)?)?)
             }
             ChatCommands::Update {
                 iden,
@@ -86,25 +76,18 @@ impl Request for ChatCommands {
                 data_binary,
             } => {
                 let request = match data_binary {
-                    Some(data_binary) => match serde_json::from_str(&data_binary) {
-                        Ok(request) => request,
-                        Err(error) => {
-                            return Err(Box::new(error));
-                        }
-                    },
-                    None => UpdateRequest {
-                        muted: muted.clone(),
-                    },
+                    Some(data_binary) => serde_json::from_str(data_binary)?,
+                    None => UpdateRequest { muted: *muted },
                 };
-                let request_builder = reqwest::blocking::Client::new()
-                    .post(format!("https://api.pushbullet.com/v2/chats/{}", iden))
-                    .json(&request);
-                Ok(request_builder)
+                Ok(serde_json::to_value(
+                    client.chats().update(iden, &request)?,
+                )?)
             }
-            ChatCommands::Delete { iden } => {
-                let request_builder = reqwest::blocking::Client::new()
-                    .delete(format!("https://api.pushbullet.com/v2/chats/{}", iden));
-                Ok(request_builder)
+            ChatCommands::Delete { iden, guard } => {
+                let chat = find(Kind::Chat, client.chats().iter(Pagination::default()), iden)?;
+                guard.delete(client, Kind::Chat, vec![chat], |iden| {
+                    client.chats().delete(iden)
+                })
             }
         }
     }
@@ -1,29 +1,93 @@
 use std::{
     env,
-    error::Error,
-    fs::{self, File},
-    io::{self, ErrorKind, Write},
-    path::{Path, PathBuf},
+    io::{self, Read},
+    process,
+    sync::Arc,
 };
 
 use clap::{Args, Parser, Subcommand};
-use reqwest::blocking::{multipart, Client, RequestBuilder};
-use serde::{Deserialize, Serialize};
-use serde_json::json;
+use indicatif::{MultiProgress, ProgressBar, ProgressDrawTarget, ProgressStyle};
+use pushbullet_rust::{
+    push::CreateRequest, Client, Page, Pagination, Paginator, PbError, Result, UploadOptions,
+    UploadRequestResponse,
+};
+use serde::Serialize;
+use serde_json::{json, Value};
 
 use super::{
-    channel::ChannelCommands, chat::ChatCommands, device::DeviceCommands, push::PushCommands,
-    subscription::SubscriptionCommands, text::TextCommands, user::UserCommands,
+    bulk::{BulkArgs, DeleteArgs, DismissArgs, ExportArgs},
+    cache::{Cache, CacheCommands},
+    channel::ChannelCommands,
+    chat::ChatCommands,
+    config::{Config, ConfigCommands, Key, Profile},
+    daemon::DaemonArgs,
+    device::DeviceCommands,
+    ephemeral::EphemeralCommands,
+    exec::ExecArgs,
+    guard::GuardArgs,
+    journal::{Kind, UndoArgs},
+    keyring,
+    output::OutputFormat,
+    pipe::PipeArgs,
+    push::PushCommands,
+    resolve::Resolver,
+    send::SendArgs,
+    stream::StreamArgs,
+    subscription::SubscriptionCommands,
+    text::TextCommands,
+    user::UserCommands,
 };
 
+const EXIT_CODES: &str = "Exit codes:
+  0   success
+  2   invalid arguments
+  3   config or access token error
+  4   network error
+  5   access token rejected (401, 403)
+  6   object not found (404)
+  7   rate limited (429)
+  8   other API error
+  9   invalid JSON
+  10  upload error
+  11  realtime stream error
+  12  local input or output error";
+
 #[derive(Parser)]
-#[command(version, about, long_about = None)]
+#[command(version, about, long_about = None, after_long_help = EXIT_CODES)]
 pub struct Cli {
+    /// Base url of the Pushbullet API, for example a local mock server. Overrides the one stored with `pb api-url`.
+    #[arg(long, global = true, env = "PB_API_URL")]
+    pub api_url: Option<String>,
+
+    /// Profile of the config file to use instead of the current one, see `pb config use-profile`.
+    #[arg(long, global = true, env = "PB_PROFILE")]
+    pub profile: Option<String>,
+
+    /// Access token to use instead of the configured one, PB_ACCESS_TOKEN taking precedence.
+    #[arg(long, global = true)]
+    pub token: Option<String>,
+
+    /// How results are printed, json unless the profile sets another output.
+    #[arg(long, global = true, value_enum)]
+    pub output: Option<OutputFormat>,
+
+    /// Line printed for every object, with {{field}} replaced by its value, for example '{{iden}} {{title}}'. Implies --output template.
+    #[arg(long, global = true)]
+    pub template: Option<String>,
+
+    /// Fields shown for every object, nested fields written as with.email.
+    #[arg(long, global = true, value_delimiter = ',')]
+    pub columns: Vec<String>,
+
+    /// Read pushes, devices, chats and subscriptions from the cache kept by pb cache sync instead of the API. Works with list and search commands.
+    #[arg(long, global = true)]
+    pub offline: bool,
+
     #[command(subcommand)]
     pub command: Commands,
 }
 
-#[derive(Args, Serialize)]
+#[derive(Args)]
 pub struct PaginationArgs {
     /// When listing objects, if you receive a cursor in the response, it means the results are on multiple pages. To request the next page of results, use this cursor as the parameter cursor in the next request.
     #[arg(long)]
@@ -32,18 +96,77 @@ pub struct PaginationArgs {
     /// You can specify a limit parameter that return a list of objects to get a smaller number of objects on each page.
     #[arg(long, default_value = "500")]
     pub limit: Option<i32>,
+
+    /// Follow the cursor until the last page and print every object as a single list.
+    #[arg(long)]
+    pub all: bool,
+
+    /// Stop after this many objects when using --all, 0 for no limit.
+    #[arg(long, default_value = "10000", requires = "all")]
+    pub max_items: usize,
 }
 
 impl PaginationArgs {
-    pub fn to_query(&self) -> Vec<(String, String)> {
-        let mut query: Vec<(String, String)> = vec![];
-        if let Some(cursor) = &self.cursor {
-            query.push((String::from("cursor"), cursor.to_owned()));
+    /// The cached objects of key, as many as a page or --max-items with --all.
+    pub fn offline(&self, key: &str, cache: &Cache) -> Result<Value> {
+        let mut items = cache.items(key)?;
+        truncate(&mut items, self.all, self.limit, self.max_items);
+        Ok(json!({ key: items }))
+    }
+
+    pub fn to_request(&self) -> Pagination {
+        Pagination {
+            modified_after: None,
+            cursor: self.cursor.clone(),
+            limit: self.limit,
         }
-        if let Some(limit) = self.limit {
-            query.push((String::from("limit"), limit.to_string()));
+    }
+}
+
+/// Recipient of a push.
+#[derive(Args)]
+pub struct TargetArgs {
+    /// Device iden of the target device, the profile's default device when no target is given.
+    #[arg(long)]
+    pub device_iden: Option<String>,
+
+    /// Email address to send the push to.
+    #[arg(long, conflicts_with = "device_iden")]
+    pub email: Option<String>,
+
+    /// Channel tag of the target channel, sends the push to all of its subscribers.
+    #[arg(long, conflicts_with_all = ["device_iden", "email"])]
+    pub channel_tag: Option<String>,
+
+    /// Device iden of the sending device, the profile's source device when not given.
+    #[arg(long)]
+    pub source_device_iden: Option<String>,
+}
+
+impl TargetArgs {
+    /// Send to device when no other target is given.
+    pub fn set_default_device(&mut self, device: &str) {
+        if self.device_iden.is_none() && self.email.is_none() && self.channel_tag.is_none() {
+            self.device_iden = Some(device.to_owned());
+        }
+    }
+
+    /// Send from device when no source is given.
+    pub fn set_default_source(&mut self, device: &str) {
+        if self.source_device_iden.is_none() {
+            self.source_device_iden = Some(device.to_owned());
+        }
+    }
+
+    /// A push to this target, to be filled with its content.
+    pub fn to_request(&self) -> CreateRequest {
+        CreateRequest {
+            device_iden: self.device_iden.clone(),
+            email: self.email.clone(),
+            channel_tag: self.channel_tag.clone(),
+            source_device_iden: self.source_device_iden.clone(),
+            ..Default::default()
         }
-        query
     }
 }
 
@@ -52,9 +175,33 @@ pub enum Commands {
     /// To access the API you'll need an access token so the server knows who you are.
     AccessToken {
         /// You can get one from your Account Settings page.
-        access_token: String,
+        #[arg(required_unless_present = "stdin")]
+        access_token: Option<String>,
+
+        /// Read the access token from the first line of stdin, keeping it out of the shell history.
+        #[arg(long, conflicts_with = "access_token")]
+        stdin: bool,
+
+        /// Encrypt the access token with a passphrase instead of storing it in plaintext in the config file. PB_KEYRING_PASSPHRASE is used as passphrase when set. The decrypted token is remembered for keyring_cache seconds in plaintext under XDG_RUNTIME_DIR.
+        #[arg(long)]
+        keyring: bool,
+    },
+
+    /// Store the base url of the API used instead of https://api.pushbullet.com/v2.
+    ApiUrl {
+        /// Base url of the API, for example http://localhost:8080/v2.
+        #[arg(required_unless_present = "reset")]
+        api_url: Option<String>,
+
+        /// Remove the stored url and go back to the default.
+        #[arg(long, conflicts_with = "api_url")]
+        reset: bool,
     },
 
+    /// Read and change the settings of the config file and switch between profiles.
+    #[command(subcommand)]
+    Config(ConfigCommands),
+
     /// Chats are created whenever you send a message to someone or a receive a message from them and there is no existing chat between you and the other user.
     #[command(subcommand)]
     Chat(ChatCommands),
@@ -66,6 +213,15 @@ pub enum Commands {
     #[command(subcommand)]
     Push(PushCommands),
 
+    /// Send a url, a file or a note, detecting the type of push from the argument.
+    Send(SendArgs),
+
+    /// Send stdin as note pushes once it is closed, for example `long_job | pb pipe --title "nightly build"`.
+    Pipe(PipeArgs),
+
+    /// Run a command and send a push with its status, runtime and last lines of output when it ends, for example `pb exec -- make release`. Exits with the status of the command.
+    Exec(ExecArgs),
+
     #[command(subcommand)]
     Channel(ChannelCommands),
 
@@ -79,130 +235,331 @@ pub enum Commands {
 
     #[command(subcommand)]
     User(UserCommands),
-}
 
-pub fn set_access_token(access_token: &str) -> io::Result<()> {
-    let home = env::var("HOME").unwrap();
-    let path = Path::new(&home).join(".config").join("pbr").join("config");
+    /// Send ephemerals, pushes that are not stored, such as universal copy/paste clips and dismissals of mirrored notifications.
+    #[command(subcommand)]
+    Ephemeral(EphemeralCommands),
 
-    let mut config_file = match File::open(&path) {
-        Ok(f) => f,
-        Err(e) if e.kind() == ErrorKind::NotFound => {
-            match fs::create_dir_all(&path.parent().unwrap()) {
-                Ok(()) => match File::create(&path) {
-                    Ok(f) => f,
-                    Err(e) => return Err(e),
+    /// Listen to the realtime event stream and print nop, tickle and push messages as they arrive.
+    Stream(StreamArgs),
+
+    /// Poll for new pushes and run the actions of the rules they match, such as running a command, saving files, copying to the clipboard or dismissing.
+    Daemon(DaemonArgs),
+
+    /// Create the objects removed by the last delete command again, from the undo journal.
+    Undo(UndoArgs),
+
+    /// Keep a local copy of pushes, devices, chats and subscriptions, synced incrementally and read with --offline.
+    #[command(subcommand)]
+    Cache(CacheCommands),
+}
+
+impl Commands {
+    /// Replace the device nicknames, chat emails, channel tags and iden prefixes given as idens by the idens they name.
+    pub fn resolve(&mut self, resolver: &mut Resolver) -> Result<()> {
+        match self {
+            Commands::Push(PushCommands::Create {
+                device_iden,
+                source_device_iden,
+                email,
+                channel_tag,
+                ..
+            }) => {
+                resolver.resolve_target(device_iden, email, channel_tag)?;
+                if let Some(source_device_iden) = source_device_iden {
+                    resolver.resolve(Kind::Device, source_device_iden)?;
+                }
+            }
+            Commands::Ephemeral(EphemeralCommands::Clip {
+                source_device_iden: Some(source_device_iden),
+                ..
+            }) => resolver.resolve(Kind::Device, source_device_iden)?,
+            Commands::Push(
+                PushCommands::Search(filter)
+                | PushCommands::Export(ExportArgs { filter, .. })
+                | PushCommands::Dismiss(DismissArgs {
+                    bulk: BulkArgs { filter, .. },
+                    ..
+                })
+                | PushCommands::Delete(DeleteArgs {
+                    bulk: BulkArgs { filter, .. },
+                    ..
+                }),
+            ) => {
+                if let Some(device) = &mut filter.device {
+                    resolver.resolve(Kind::Device, device)?;
+                }
+            }
+            Commands::Send(SendArgs { target, .. })
+            | Commands::Pipe(PipeArgs { target, .. })
+            | Commands::Exec(ExecArgs { target, .. }) => {
+                resolver.resolve_target(
+                    &mut target.device_iden,
+                    &mut target.email,
+                    &mut target.channel_tag,
+                )?;
+                if let Some(source_device_iden) = &mut target.source_device_iden {
+                    resolver.resolve(Kind::Device, source_device_iden)?;
+                }
+            }
+            Commands::Device(
+                DeviceCommands::Update { iden, .. } | DeviceCommands::Delete { iden, .. },
+            ) => resolver.resolve(Kind::Device, iden)?,
+            Commands::Chat(
+                ChatCommands::Update { iden, .. } | ChatCommands::Delete { iden, .. },
+            ) => resolver.resolve(Kind::Chat, iden)?,
+            Commands::Subscription(
+                SubscriptionCommands::Update { iden, .. }
+                | SubscriptionCommands::Delete { iden, .. },
+            ) => resolver.resolve(Kind::Subscription, iden)?,
+            Commands::Text(
+                TextCommands::Create {
+                    target_device_iden, ..
+                }
+                | TextCommands::Update {
+                    target_device_iden, ..
                 },
-                Err(e) => return Err(e),
+            ) => {
+                if let Some(target_device_iden) = target_device_iden {
+                    resolver.resolve(Kind::Device, target_device_iden)?;
+                }
             }
+            _ => (),
         }
-        Err(e) => return Err(e),
-    };
+        Ok(())
+    }
 
-    config_file.write_all(access_token.as_bytes())?;
+    /// Safety options of the commands deleting objects.
+    pub fn guard_mut(&mut self) -> Option<&mut GuardArgs> {
+        match self {
+            Commands::Push(PushCommands::Delete(args)) => Some(&mut args.guard),
+            Commands::Push(PushCommands::DeleteAll { guard, .. })
+            | Commands::Device(DeviceCommands::Delete { guard, .. })
+            | Commands::Chat(ChatCommands::Delete { guard, .. })
+            | Commands::Subscription(SubscriptionCommands::Delete { guard, .. })
+            | Commands::Text(TextCommands::Delete { guard, .. }) => Some(guard),
+            _ => None,
+        }
+    }
 
-    Ok(())
+    /// Answer a list or search command from the cache.
+    pub fn offline(&self, cache: &Cache) -> Result<Value> {
+        match self {
+            Commands::Push(PushCommands::List(args)) => args.offline(cache),
+            Commands::Push(PushCommands::Search(args)) => args.offline(cache),
+            Commands::Device(DeviceCommands::List(args)) => args.offline("devices", cache),
+            Commands::Chat(ChatCommands::List(args)) => args.offline("chats", cache),
+            Commands::Subscription(SubscriptionCommands::List(args)) => {
+                args.offline("subscriptions", cache)
+            }
+            Commands::Cache(cache_commands) => cache_commands.run(None, cache),
+            _ => Err(PbError::Config(String::from(
+                "--offline only works with list and search commands",
+            ))),
+        }
+    }
 }
 
-pub fn read_access_token() -> io::Result<String> {
-    let home = env::var("HOME").unwrap();
-    let path = Path::new(&home).join(".config").join("pbr").join("config");
-    Ok(fs::read_to_string(path)?)
+pub fn set_access_token(
+    mut config: Config,
+    profile: &str,
+    access_token: &str,
+    keyring: bool,
+) -> Result<()> {
+    let settings = config.profile_mut(profile);
+    if keyring {
+        keyring::store(profile, access_token.trim())?;
+        settings.access_token = None;
+        settings.keyring = Some(true);
+    } else {
+        settings.set(Key::AccessToken, Some(access_token))?;
+        settings.keyring = None;
+    }
+    config.save()
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct UploadRequestRequest {
-    pub file_name: Option<String>,
+/// Access token from, in order, PB_ACCESS_TOKEN, the --token flag, the token_command, the keyring and the access_token of the profile.
+pub fn read_access_token(
+    profile_name: &str,
+    profile: &Profile,
+    token: Option<&str>,
+) -> Result<String> {
+    if let Some(access_token) = env::var("PB_ACCESS_TOKEN")
+        .ok()
+        .filter(|access_token| !access_token.trim().is_empty())
+    {
+        return Ok(access_token.trim().to_owned());
+    }
+    if let Some(access_token) = token {
+        return Ok(access_token.trim().to_owned());
+    }
+    if let Some(token_command) = &profile.token_command {
+        return run_token_command(token_command);
+    }
+    if profile.keyring == Some(true) {
+        return keyring::load(profile_name, profile.keyring_cache);
+    }
+    profile.access_token.clone().ok_or_else(|| {
+        PbError::Config(format!(
+            "no access token configured in profile {profile_name}, set one with `pb access-token`"
+        ))
+    })
+}
 
-    pub file_type: Option<String>,
+/// Read the whole of stdin, invalid UTF-8 being replaced.
+pub fn read_stdin() -> Result<String> {
+    let mut input = vec![];
+    io::stdin()
+        .read_to_end(&mut input)
+        .map_err(|e| PbError::Io(format!("stdin: {e}")))?;
+    Ok(String::from_utf8_lossy(&input).into_owned())
 }
 
-#[derive(Debug, Deserialize)]
-pub struct UploadRequestResponse {
-    pub file_name: String,
-    pub file_type: String,
-    pub file_url: String,
-    pub upload_url: String,
+/// Read the access token from the first line of stdin.
+pub fn read_stdin_token() -> Result<String> {
+    let mut line = String::new();
+    io::stdin()
+        .read_line(&mut line)
+        .map_err(|e| PbError::Config(format!("stdin: {e}")))?;
+    match line.trim() {
+        "" => Err(PbError::Config(String::from("no access token on stdin"))),
+        access_token => Ok(access_token.to_owned()),
+    }
 }
 
-pub fn upload_request(
-    access_token: &str,
-    file_name: String,
-    file_type: Option<String>,
-) -> Result<UploadRequestResponse, Box<dyn Error>> {
-    let path_buf = PathBuf::from(&file_name);
-
-    let final_file_type: String;
-    match file_type {
-        Some(file_type) => final_file_type = file_type,
-        None => {
-            let flags = magic::cookie::Flags::MIME_TYPE;
-            let cookie = magic::Cookie::open(flags).unwrap();
-
-            let database = &Default::default();
-            let cookie = cookie.load(database).unwrap();
-            final_file_type = cookie.file(&file_name).unwrap();
-        }
+/// Run token_command with sh and use the first line of its output as access token.
+fn run_token_command(token_command: &str) -> Result<String> {
+    let command_error =
+        |e: String| PbError::Config(format!("token_command `{token_command}`: {e}"));
+    let output = process::Command::new("sh")
+        .args(["-c", token_command])
+        .stderr(process::Stdio::inherit())
+        .output()
+        .map_err(|e| command_error(e.to_string()))?;
+    if !output.status.success() {
+        return Err(command_error(output.status.to_string()));
+    }
+    let stdout = String::from_utf8(output.stdout).map_err(|e| command_error(e.to_string()))?;
+    match stdout.lines().next().map(str::trim) {
+        Some(access_token) if !access_token.is_empty() => Ok(access_token.to_owned()),
+        _ => Err(command_error(String::from("printed no access token"))),
     }
-    let real_file_name = path_buf.file_name().unwrap();
-    let request = UploadRequestRequest {
-        file_name: Some(real_file_name.to_os_string().into_string().unwrap()),
-        file_type: Some(final_file_type),
+}
+
+pub fn set_api_url(mut config: Config, profile: &str, api_url: Option<&str>) -> Result<()> {
+    config.profile_mut(profile).set(Key::ApiUrl, api_url)?;
+    config.save()
+}
+
+/// Progress bars of uploads and downloads, drawn on stderr when it is a terminal unless quiet.
+pub fn progress_bars(quiet: bool) -> MultiProgress {
+    match quiet {
+        true => MultiProgress::with_draw_target(ProgressDrawTarget::hidden()),
+        false => MultiProgress::new(),
+    }
+}
+
+/// Progress bar of a file transfer among bars, labelled with message.
+pub fn progress_bar(bars: &MultiProgress, message: &str) -> ProgressBar {
+    let bar = bars.add(
+        ProgressBar::new(0).with_style(
+            ProgressStyle::with_template(
+                "{msg} [{bar:30}] {bytes}/{total_bytes} {bytes_per_sec} {eta}",
+            )
+            .unwrap()
+            .progress_chars("=> "),
+        ),
+    );
+    bar.set_message(message.to_owned());
+    bar
+}
+
+/// Upload a file for a file push, with a progress bar among bars.
+pub fn upload(
+    client: &Client,
+    path: &str,
+    file_type: Option<String>,
+    bars: &MultiProgress,
+) -> Result<UploadRequestResponse> {
+    let bar = progress_bar(bars, path);
+    let progress = bar.clone();
+    let options = UploadOptions {
+        file_type,
+        progress: Some(Arc::new(move |sent, size| {
+            progress.set_length(size);
+            progress.set_position(sent);
+        })),
+        ..Default::default()
     };
-    let request_builder = reqwest::blocking::Client::new()
-        .post("https://api.pushbullet.com/v2/upload-request")
-        .header("Access-Token", access_token)
-        .json(&request);
-
-    match request_builder.send() {
-        Ok(res) => match res.text() {
-            Ok(text) => match serde_json::from_str(&text) {
-                Ok(value) => Ok(value),
-                Err(e) => Err(Box::new(e)),
-            },
-            Err(e) => Err(Box::new(e)),
-        },
-        Err(e) => Err(Box::new(e)),
-    }
-}
-
-pub fn upload(access_token: &str, file_name: &str, upload_url: &str) -> Result<(), Box<dyn Error>> {
-    let form = match multipart::Form::new().file("file", &file_name) {
-        Ok(form) => form,
-        Err(error) => {
-            return Err(Box::new(error));
-        }
+    let response = client.upload_file(path, &options);
+    bar.finish_and_clear();
+    bars.remove(&bar);
+    response
+}
+
+/// Keep the objects of a page of limit objects, or max_items objects (0 for no limit) when all pages are requested.
+pub fn truncate<T>(items: &mut Vec<T>, all: bool, limit: Option<i32>, max_items: usize) {
+    let len = match (all, limit) {
+        (true, _) if max_items > 0 => max_items,
+        (false, Some(limit)) if limit > 0 => limit as usize,
+        _ => items.len(),
     };
-    let response_result = reqwest::blocking::Client::new()
-        .post(upload_url)
-        .header("Access-Token", access_token)
-        .multipart(form)
-        .send();
-    match response_result {
-        Ok(_) => Ok(()),
-        Err(error) => {
-            return Err(Box::new(error));
+    items.truncate(len);
+}
+
+/// Collect the objects of every page under key, stopping after max_items objects (0 for no limit).
+pub fn collect_pages<P>(
+    key: &str,
+    mut paginator: Paginator<'_, P>,
+    max_items: usize,
+) -> Result<Value>
+where
+    P: Page,
+    P::Item: Serialize,
+{
+    let mut items = vec![];
+    while max_items == 0 || items.len() < max_items {
+        match paginator.next() {
+            Some(item) => items.push(item?),
+            None => break,
         }
     }
+    if paginator.has_more() {
+        eprintln!("Stopped after {max_items} objects, more are available (see --max-items)");
+    }
+    Ok(json!({ key: items }))
 }
 
-pub trait Request {
-    fn request(&self, access_token: &str) -> Result<String, Box<dyn Error>> {
-        let request_builder = match self.build_request(access_token) {
-            Ok(request_builder) => request_builder.header("Access-Token", access_token),
-            Err(error) => {
-                return Err(error);
-            }
-        };
-
-        match request_builder.send() {
-            Ok(response) => match response.text() {
-                Ok(text) => Ok(text),
-                Err(error) => Err(Box::new(error)),
-            },
-            Err(error) => Err(Box::new(error)),
+/// Exit code of the process for an error, so scripts can tell failures apart.
+pub fn exit_code(error: &PbError) -> i32 {
+    match error {
+        PbError::Config(_) => 3,
+        PbError::Transport(_) => 4,
+        PbError::Api {
+            status: 401 | 403, ..
+        } => 5,
+        PbError::Api { status: 404, .. } => 6,
+        PbError::Api { status: 429, .. } => 7,
+        PbError::Api { .. } => 8,
+        PbError::Decode(_) => 9,
+        PbError::Upload(_) => 10,
+        PbError::Stream(_) => 11,
+        PbError::Io(_) => 12,
+    }
+}
+
+/// Remove the null fields of absent optional values, so printed objects only carry what the server sent.
+pub fn strip_nulls(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            map.retain(|_, v| !v.is_null());
+            map.values_mut().for_each(strip_nulls);
         }
+        Value::Array(values) => values.iter_mut().for_each(strip_nulls),
+        _ => (),
     }
+}
 
-    fn build_request(&self, access_token: &str) -> Result<RequestBuilder, Box<dyn Error>>;
+pub trait Request {
+    fn request(&self, client: &Client) -> Result<Value>;
 }
@@ -0,0 +1,348 @@
+use std::{
+    collections::BTreeMap,
+    env,
+    fs::{self, OpenOptions},
+    io::{self, ErrorKind, Write},
+    path::{Path, PathBuf},
+};
+
+use clap::{Subcommand, ValueEnum};
+use pushbullet_rust::{PbError, Result};
+use serde::{Deserialize, Serialize};
+
+use super::output::OutputFormat;
+
+/// Profile used when none is selected with --profile, PB_PROFILE or `pb config use-profile`.
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// Settings stored in ~/.config/pbr/config.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Config {
+    /// Profile used when none is given on the command line.
+    pub current_profile: Option<String>,
+
+    #[serde(default)]
+    pub profiles: BTreeMap<String, Profile>,
+
+    /// Access token of configs written before profiles existed, moved to the default profile on load.
+    #[serde(default, skip_serializing)]
+    access_token: Option<String>,
+
+    /// Api url of configs written before profiles existed, moved to the default profile on load.
+    #[serde(default, skip_serializing)]
+    api_url: Option<String>,
+
+    /// The config file could not be read, it is kept as config.bak when this config is saved over it.
+    #[serde(skip)]
+    replaces_invalid: bool,
+}
+
+/// Settings of one account, such as personal or team.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Profile {
+    /// Access token used to authenticate every request.
+    pub access_token: Option<String>,
+
+    /// Shell command printing the access token, such as `pass show pushbullet`, used instead of access_token.
+    pub token_command: Option<String>,
+
+    /// The access token is encrypted with a passphrase in the keyring file of the profile, see `pb access-token --keyring`.
+    pub keyring: Option<bool>,
+
+    /// Seconds the decrypted keyring token is remembered, 900 when unset and 0 to ask for the passphrase every time. It is remembered in plaintext in a file only readable by the current user under XDG_RUNTIME_DIR.
+    pub keyring_cache: Option<u64>,
+
+    /// Device iden pushes are sent to when no target is given.
+    pub device: Option<String>,
+
+    /// Device iden this machine was registered as with `pb device register`, the source of the pushes it creates.
+    pub source_device: Option<String>,
+
+    /// Output format, one of json, pretty, table, jsonl or template.
+    pub output: Option<String>,
+
+    /// Base url of the API, replacing https://api.pushbullet.com/v2.
+    pub api_url: Option<String>,
+
+    /// Seconds before a request is abandoned.
+    pub timeout: Option<u64>,
+
+    /// Seconds before establishing a connection is abandoned.
+    pub connect_timeout: Option<u64>,
+}
+
+/// A setting of a profile, named as in the config file.
+#[derive(Debug, Clone, Copy, ValueEnum)]
+#[value(rename_all = "snake_case")]
+pub enum Key {
+    AccessToken,
+    TokenCommand,
+    Keyring,
+    KeyringCache,
+    Device,
+    SourceDevice,
+    Output,
+    ApiUrl,
+    Timeout,
+    ConnectTimeout,
+}
+
+#[derive(Subcommand)]
+pub enum ConfigCommands {
+    /// Print a setting of the selected profile.
+    Get { key: Key },
+
+    /// Change a setting of the selected profile, creating the profile if needed. An empty value removes the setting.
+    Set { key: Key, value: String },
+
+    /// Print every profile and its settings, the current one marked with *.
+    List,
+
+    /// Select the profile used when --profile and PB_PROFILE are not given.
+    UseProfile { name: String },
+}
+
+impl Config {
+    pub fn path() -> Result<PathBuf> {
+        let home = env::var("HOME").map_err(|e| PbError::Config(format!("HOME: {e}")))?;
+        Ok(Path::new(&home).join(".config").join("pbr").join("config"))
+    }
+
+    /// Read the config file, an absent file being an empty config.
+    pub fn load() -> Result<Config> {
+        let path = Config::path()?;
+        match Config::read(&path)? {
+            Some(text) => Config::parse(&text)
+                .map_err(|e| PbError::Config(format!("{}: {e}", path.display()))),
+            None => Ok(Config::default()),
+        }
+    }
+
+    /// Read the config file to change it, a file that cannot be parsed being an empty config so the commands writing settings can repair it.
+    pub fn load_for_update() -> Result<Config> {
+        let path = Config::path()?;
+        let Some(text) = Config::read(&path)? else {
+            return Ok(Config::default());
+        };
+        Config::parse(&text).or_else(|e| {
+            eprintln!("Ignoring invalid config {}: {e}", path.display());
+            Ok(Config {
+                replaces_invalid: true,
+                ..Default::default()
+            })
+        })
+    }
+
+    /// Text of the config file, None when there is none.
+    fn read(path: &Path) -> Result<Option<String>> {
+        match fs::read_to_string(path) {
+            Ok(text) => Ok(Some(text)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(PbError::Config(format!("{}: {e}", path.display()))),
+        }
+    }
+
+    fn parse(text: &str) -> std::result::Result<Config, toml::de::Error> {
+        let mut config = match toml::from_str::<Config>(text) {
+            Ok(config) => config,
+            // Older versions stored the bare access token as the whole file.
+            Err(_) if is_bare_token(text) => Config {
+                access_token: Some(text.trim().to_owned()),
+                ..Default::default()
+            },
+            Err(e) => return Err(e),
+        };
+
+        let (access_token, api_url) = (config.access_token.take(), config.api_url.take());
+        if access_token.is_some() || api_url.is_some() {
+            let profile = config.profile_mut(DEFAULT_PROFILE);
+            profile.access_token = profile.access_token.take().or(access_token);
+            profile.api_url = profile.api_url.take().or(api_url);
+        }
+        Ok(config)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = Config::path()?;
+        if self.replaces_invalid {
+            let backup = path.with_extension("bak");
+            fs::rename(&path, &backup)
+                .map_err(|e| PbError::Config(format!("{}: {e}", backup.display())))?;
+            eprintln!("Kept the invalid config as {}", backup.display());
+        }
+        let text = toml::to_string(self).map_err(|e| PbError::Config(e.to_string()))?;
+        write_private(&path, text.as_bytes())
+            .map_err(|e| PbError::Config(format!("{}: {e}", path.display())))
+    }
+
+    /// Name of the profile to use, the one given on the command line first.
+    pub fn profile_name(&self, name: Option<&str>) -> String {
+        name.or(self.current_profile.as_deref())
+            .unwrap_or(DEFAULT_PROFILE)
+            .to_owned()
+    }
+
+    /// Settings of a profile, empty when it does not exist.
+    pub fn profile(&self, name: &str) -> Profile {
+        self.profiles.get(name).cloned().unwrap_or_default()
+    }
+
+    pub fn profile_mut(&mut self, name: &str) -> &mut Profile {
+        self.profiles.entry(name.to_owned()).or_default()
+    }
+}
+
+impl Profile {
+    pub fn get(&self, key: Key) -> Option<String> {
+        match key {
+            Key::AccessToken => self.access_token.clone(),
+            Key::TokenCommand => self.token_command.clone(),
+            Key::Keyring => self.keyring.map(|keyring| keyring.to_string()),
+            Key::KeyringCache => self.keyring_cache.map(|seconds| seconds.to_string()),
+            Key::Device => self.device.clone(),
+            Key::SourceDevice => self.source_device.clone(),
+            Key::Output => self.output.clone(),
+            Key::ApiUrl => self.api_url.clone(),
+            Key::Timeout => self.timeout.map(|timeout| timeout.to_string()),
+            Key::ConnectTimeout => self.connect_timeout.map(|timeout| timeout.to_string()),
+        }
+    }
+
+    /// Change a setting from its text value, None removing it.
+    pub fn set(&mut self, key: Key, value: Option<&str>) -> Result<()> {
+        let value = value.map(str::trim).filter(|value| !value.is_empty());
+        let seconds = |value: Option<&str>| {
+            value
+                .map(|value| {
+                    value.parse().map_err(|_| {
+                        PbError::Config(format!("invalid number of seconds \"{value}\""))
+                    })
+                })
+                .transpose()
+        };
+        match key {
+            Key::AccessToken => self.access_token = value.map(str::to_owned),
+            Key::TokenCommand => self.token_command = value.map(str::to_owned),
+            Key::Keyring => {
+                self.keyring = value
+                    .map(|value| {
+                        value.parse().map_err(|_| {
+                            PbError::Config(format!(
+                                "invalid boolean \"{value}\", expected true or false"
+                            ))
+                        })
+                    })
+                    .transpose()?
+            }
+            Key::KeyringCache => self.keyring_cache = seconds(value)?,
+            Key::Device => self.device = value.map(str::to_owned),
+            Key::SourceDevice => self.source_device = value.map(str::to_owned),
+            Key::Output => {
+                if let Some(output) = value {
+                    OutputFormat::parse(output)?;
+                }
+                self.output = value.map(str::to_owned)
+            }
+            Key::ApiUrl => {
+                self.api_url = value.map(|url| url.trim_end_matches('/').to_owned());
+            }
+            Key::Timeout => self.timeout = seconds(value)?,
+            Key::ConnectTimeout => self.connect_timeout = seconds(value)?,
+        }
+        Ok(())
+    }
+}
+
+impl ConfigCommands {
+    /// Read or change the config file, profile being the one selected on the command line.
+    pub fn run(&self, mut config: Config, profile: &str) -> Result<()> {
+        match self {
+            ConfigCommands::Get { key } => match config.profile(profile).get(*key) {
+                Some(value) => println!("{value}"),
+                None => {
+                    return Err(PbError::Config(format!(
+                        "{} is not set in profile {profile}",
+                        key_name(*key)
+                    )))
+                }
+            },
+            ConfigCommands::Set { key, value } => {
+                config.profile_mut(profile).set(*key, Some(value))?;
+                config.save()?;
+            }
+            ConfigCommands::List => {
+                let current = config.profile_name(None);
+                for (name, settings) in &config.profiles {
+                    let marker = if *name == current { '*' } else { ' ' };
+                    println!("{marker} {name}");
+                    for key in Key::value_variants() {
+                        let Some(mut value) = settings.get(*key) else {
+                            continue;
+                        };
+                        if let Key::AccessToken = key {
+                            value = mask(&value);
+                        }
+                        println!("    {} = {value}", key_name(*key));
+                    }
+                }
+            }
+            ConfigCommands::UseProfile { name } => {
+                check_profile(name)?;
+                if !config.profiles.contains_key(name) {
+                    return Err(PbError::Config(format!(
+                        "no profile named {name}, create it with `pb --profile {name} access-token`"
+                    )));
+                }
+                config.current_profile = Some(name.clone());
+                config.save()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Replace the content of a file only readable by the current user, as it holds access tokens.
+pub fn write_private(path: &Path, contents: &[u8]) -> io::Result<()> {
+    fs::create_dir_all(path.parent().unwrap())?;
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+        options.mode(0o600);
+        // The mode only applies to new files, older versions created the config world readable.
+        if path.exists() {
+            fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
+        }
+    }
+    options.open(path)?.write_all(contents)
+}
+
+/// A profile name, checked to only hold letters, digits, - and _ as it names the files of the profile.
+pub fn check_profile(name: &str) -> Result<&str> {
+    if !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Ok(name);
+    }
+    Err(PbError::Config(format!(
+        "invalid profile name {name:?}, use only letters, digits, - and _"
+    )))
+}
+
+fn key_name(key: Key) -> String {
+    key.to_possible_value().unwrap().get_name().to_owned()
+}
+
+/// Hide all but the start of an access token.
+fn mask(access_token: &str) -> String {
+    let visible: String = access_token.chars().take(4).collect();
+    format!("{visible}***")
+}
+
+fn is_bare_token(text: &str) -> bool {
+    let text = text.trim();
+    !text.is_empty() && !text.contains(|c: char| c.is_whitespace() || c == '=')
+}
@@ -0,0 +1,371 @@
+use std::{
+    env, fs,
+    io::{self, ErrorKind, Write},
+    path::{Path, PathBuf},
+    process::{self, Stdio},
+    thread,
+    time::Duration,
+};
+
+use clap::Args;
+use pushbullet_rust::{
+    push::{ListRequest, Push, UpdateRequest},
+    Client, DownloadOptions, PbError, Result,
+};
+use regex::Regex;
+use serde::{de, Deserialize, Deserializer, Serialize};
+use serde_json::{json, Value};
+
+use super::{
+    config::{check_profile, write_private},
+    journal::Kind,
+    resolve::Resolver,
+};
+
+/// Commands tried in turn to copy to the clipboard when --clipboard-command is not given.
+const CLIPBOARD_COMMANDS: [&str; 4] = [
+    "wl-copy",
+    "xclip -selection clipboard",
+    "xsel --clipboard --input",
+    "pbcopy",
+];
+
+#[derive(Args)]
+pub struct DaemonArgs {
+    /// Rules file, YAML when named *.yaml or *.yml and TOML otherwise, with one [[rule]] table per rule.
+    #[arg(long, value_name = "FILE")]
+    pub rules: PathBuf,
+
+    /// Seconds between two polls of the pushes.
+    #[arg(long, value_name = "SECONDS", default_value = "30")]
+    pub interval: u64,
+
+    /// Poll once and exit.
+    #[arg(long)]
+    pub once: bool,
+
+    /// Shell command the text of a push is piped to by rules with clipboard = true, by default the first of wl-copy, xclip, xsel and pbcopy found.
+    #[arg(long, value_name = "COMMAND")]
+    pub clipboard_command: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Rules {
+    #[serde(default)]
+    rule: Vec<Rule>,
+}
+
+/// Conditions an incoming push must all meet, and the actions run on it when it does.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Rule {
+    /// Printed with the pushes the rule matched, by default its position in the file.
+    name: Option<String>,
+
+    /// Iden, nickname or iden prefix of the device the push is sent to. Pushes sent to every device match too.
+    device: Option<String>,
+
+    /// Email address or name of the sender.
+    sender: Option<String>,
+
+    /// Type of the push, one of "note", "file", "link".
+    #[serde(rename = "type")]
+    t: Option<String>,
+
+    /// Regular expression the title must match.
+    #[serde(default, deserialize_with = "regex")]
+    title: Option<Regex>,
+
+    /// Directory the file of file pushes is saved in.
+    save_to: Option<PathBuf>,
+
+    /// Copy the url, body or file url of the push to the clipboard.
+    #[serde(default)]
+    clipboard: bool,
+
+    /// Shell command run with every field of the push in PB_PUSH_<FIELD> environment variables, such as PB_PUSH_TITLE, and the saved file in PB_PUSH_PATH.
+    command: Option<String>,
+
+    /// Dismiss the push once the other actions succeeded.
+    #[serde(default)]
+    dismiss: bool,
+}
+
+fn regex<'de, D>(deserializer: D) -> std::result::Result<Option<Regex>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Option::<String>::deserialize(deserializer)?
+        .map(|pattern| Regex::new(&pattern).map_err(de::Error::custom))
+        .transpose()
+}
+
+/// Newest modified timestamp handled, so a restarted daemon goes on where it stopped.
+#[derive(Serialize, Deserialize)]
+struct State {
+    modified: f64,
+}
+
+impl DaemonArgs {
+    /// Poll the pushes modified since the last poll and run the actions of the rules matching the new ones, printing a JSON line per match.
+    pub fn run(&self, client: &Client, profile: &str, resolver: &mut Resolver) -> Result<()> {
+        let mut rules = self.load_rules()?;
+        for rule in &mut rules {
+            if let Some(device) = &mut rule.device {
+                resolver.resolve(Kind::Device, device)?;
+            }
+        }
+
+        let state_path = state_path(profile)?;
+        let mut modified = match load_state(&state_path)? {
+            Some(state) => state.modified,
+            // Started for the first time: only pushes from now on.
+            None => newest_modified(client)?,
+        };
+        loop {
+            match self.poll(client, &rules, modified) {
+                Ok(newest) => {
+                    modified = newest;
+                    let state = serde_json::to_vec(&State { modified })?;
+                    write_private(&state_path, &state)
+                        .map_err(|e| PbError::Io(format!("{}: {e}", state_path.display())))?;
+                }
+                Err(
+                    e @ PbError::Api {
+                        status: 401 | 403, ..
+                    },
+                ) => return Err(e),
+                Err(e) if self.once => return Err(e),
+                Err(e) => eprintln!("Polling failed: {e}, retrying in {}s", self.interval),
+            }
+            if self.once {
+                return Ok(());
+            }
+            thread::sleep(Duration::from_secs(self.interval));
+        }
+    }
+
+    fn load_rules(&self) -> Result<Vec<Rule>> {
+        let rules_error = |e: String| PbError::Config(format!("{}: {e}", self.rules.display()));
+        let text = fs::read_to_string(&self.rules).map_err(|e| rules_error(e.to_string()))?;
+        let rules: Rules = match self.rules.extension().and_then(|ext| ext.to_str()) {
+            Some("yaml" | "yml") => {
+                serde_yaml::from_str(&text).map_err(|e| rules_error(e.to_string()))?
+            }
+            _ => toml::from_str(&text).map_err(|e| rules_error(e.to_string()))?,
+        };
+        Ok(rules.rule)
+    }
+
+    /// Handle the pushes created after modified, oldest first, returning the newest modified timestamp seen.
+    fn poll(&self, client: &Client, rules: &[Rule], modified: f64) -> Result<f64> {
+        let request = ListRequest {
+            modified_after: Some(modified.to_string()),
+            active: None,
+            cursor: None,
+            limit: Some(500),
+        };
+        let mut pushes = client.pushes().iter(request).collect::<Result<Vec<_>>>()?;
+        pushes.reverse();
+
+        let mut newest = modified;
+        for push in pushes {
+            newest = newest.max(push.modified);
+            // Pushes modified since, such as dismissed ones, were already handled when created.
+            if !push.active || push.created <= modified {
+                continue;
+            }
+            for (i, rule) in rules.iter().enumerate() {
+                if rule.matches(&push) {
+                    let name = rule.name.clone().unwrap_or_else(|| (i + 1).to_string());
+                    let res = self.apply(client, rule, &name, &push);
+                    println!("{}", serde_json::to_string(&res)?);
+                }
+            }
+        }
+        Ok(newest)
+    }
+
+    /// Run the actions of rule on push, reporting failed ones on stderr without stopping.
+    fn apply(&self, client: &Client, rule: &Rule, name: &str, push: &Push) -> Value {
+        let mut results = vec![];
+        let mut path = None;
+        if let Some(dir) = &rule.save_to {
+            let saved = save(client, dir, push);
+            path = saved.as_ref().ok().cloned();
+            results.push(("save_to", saved.map(|_| ())));
+        }
+        if rule.clipboard {
+            results.push(("clipboard", self.copy(push)));
+        }
+        if let Some(command) = &rule.command {
+            results.push(("command", run_command(command, push, path.as_deref())));
+        }
+        if rule.dismiss && results.iter().all(|(_, res)| res.is_ok()) {
+            let request = UpdateRequest {
+                dismissed: Some(true),
+            };
+            let dismissed = client.pushes().update(&push.iden, &request).map(|_| ());
+            results.push(("dismiss", dismissed));
+        }
+
+        let mut actions = vec![];
+        for (action, res) in results {
+            match res {
+                Ok(()) => actions.push(action),
+                Err(e) => eprintln!("Rule {name}: {action} failed on push {}: {e}", push.iden),
+            }
+        }
+        json!({
+            "iden": push.iden,
+            "rule": name,
+            "actions": actions,
+            "path": path.map(|path| path.to_string_lossy().into_owned()),
+        })
+    }
+
+    /// Pipe the url, body or file url of push to the clipboard command.
+    fn copy(&self, push: &Push) -> Result<()> {
+        let text = [&push.url, &push.body, &push.file_url, &push.title]
+            .into_iter()
+            .find_map(|text| text.as_deref())
+            .unwrap_or_default();
+        let commands = match &self.clipboard_command {
+            Some(command) => vec![command.as_str()],
+            None => CLIPBOARD_COMMANDS.to_vec(),
+        };
+        for command in commands {
+            let mut words = command.split_whitespace();
+            let program = words.next().unwrap_or_default();
+            let spawned = match &self.clipboard_command {
+                Some(_) => process::Command::new("sh")
+                    .args(["-c", command])
+                    .stdin(Stdio::piped())
+                    .spawn(),
+                None => process::Command::new(program)
+                    .args(words)
+                    .stdin(Stdio::piped())
+                    .spawn(),
+            };
+            let mut child = match spawned {
+                // Not installed, try the next one.
+                Err(e) if e.kind() == ErrorKind::NotFound && self.clipboard_command.is_none() => {
+                    continue
+                }
+                spawned => spawned.map_err(|e| PbError::Io(format!("`{command}`: {e}")))?,
+            };
+            let written = child.stdin.take().unwrap().write_all(text.as_bytes());
+            let status = child
+                .wait()
+                .map_err(|e| PbError::Io(format!("`{command}`: {e}")))?;
+            written.map_err(|e| PbError::Io(format!("`{command}`: {e}")))?;
+            if !status.success() {
+                return Err(PbError::Io(format!("`{command}` {status}")));
+            }
+            return Ok(());
+        }
+        Err(PbError::Io(String::from(
+            "no clipboard command found, pass --clipboard-command",
+        )))
+    }
+}
+
+impl Rule {
+    fn matches(&self, push: &Push) -> bool {
+        let device = match (&self.device, &push.target_device_iden) {
+            (Some(device), Some(target)) => device == target,
+            _ => true,
+        };
+        let sender = self.sender.as_ref().is_none_or(|sender| {
+            [
+                &push.sender_email,
+                &push.sender_email_normalized,
+                &push.sender_name,
+            ]
+            .into_iter()
+            .flatten()
+            .any(|value| value.eq_ignore_ascii_case(sender))
+        });
+        device
+            && sender
+            && (self.t.is_none() || self.t == push.t)
+            && self
+                .title
+                .as_ref()
+                .is_none_or(|title| title.is_match(push.title.as_deref().unwrap_or_default()))
+    }
+}
+
+/// Download the file of push into dir.
+fn save(client: &Client, dir: &Path, push: &Push) -> Result<PathBuf> {
+    let file_url = push
+        .file_url
+        .as_deref()
+        .ok_or_else(|| PbError::Io(format!("push {} has no file", push.iden)))?;
+    let file_name = push.file_name.as_deref().unwrap_or("download");
+    client.download_file(file_url, dir, file_name, &DownloadOptions::default())
+}
+
+/// Run command with sh, its output going to stderr to keep stdout for the JSON lines.
+fn run_command(command: &str, push: &Push, path: Option<&Path>) -> Result<()> {
+    let mut child = process::Command::new("sh");
+    child
+        .args(["-c", command])
+        .stdin(Stdio::null())
+        .stdout(io::stderr());
+    if let Value::Object(fields) = serde_json::to_value(push)? {
+        for (field, value) in fields {
+            let value = match value {
+                Value::String(value) => value,
+                Value::Null => continue,
+                value => value.to_string(),
+            };
+            child.env(format!("PB_PUSH_{}", field.to_uppercase()), value);
+        }
+    }
+    if let Some(path) = path {
+        child.env("PB_PUSH_PATH", path);
+    }
+    let status = child
+        .status()
+        .map_err(|e| PbError::Io(format!("`{command}`: {e}")))?;
+    if !status.success() {
+        return Err(PbError::Io(format!("`{command}` {status}")));
+    }
+    Ok(())
+}
+
+/// ~/.local/share/pbr/<profile>/daemon.json, next to the undo journal.
+fn state_path(profile: &str) -> Result<PathBuf> {
+    let home = env::var("HOME").map_err(|e| PbError::Config(format!("HOME: {e}")))?;
+    Ok(Path::new(&home)
+        .join(".local")
+        .join("share")
+        .join("pbr")
+        .join(check_profile(profile)?)
+        .join("daemon.json"))
+}
+
+fn load_state(path: &Path) -> Result<Option<State>> {
+    let state_error = |e: String| PbError::Io(format!("{}: {e}", path.display()));
+    match fs::read(path) {
+        Ok(bytes) => Ok(Some(
+            serde_json::from_slice(&bytes).map_err(|e| state_error(e.to_string()))?,
+        )),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(state_error(e.to_string())),
+    }
+}
+
+/// Modified timestamp of the newest push, 0 when there is none.
+fn newest_modified(client: &Client) -> Result<f64> {
+    let request = ListRequest {
+        modified_after: None,
+        active: None,
+        cursor: None,
+        limit: Some(1),
+    };
+    let pushes = client.pushes().list(&request)?;
+    Ok(pushes.pushes.first().map_or(0.0, |push| push.modified))
+}
@@ -1,10 +1,17 @@
-use std::error::Error;
-
-use clap::Subcommand;
-use reqwest::blocking::RequestBuilder;
-use serde::{Deserialize, Serialize};
-
-use super::{PaginationArgs, Request};
+use clap::{Args, Subcommand};
+use pushbullet_rust::{
+    device::{CreateRequest, UpdateRequest},
+    Client, Pagination, Result,
+};
+use serde_json::Value;
+
+use super::{
+    collect_pages,
+    config::{Config, Key},
+    guard::{find, GuardArgs},
+    journal::Kind,
+    PaginationArgs,
+};
 
 #[derive(Subcommand)]
 pub enum DeviceCommands {
@@ -79,87 +86,70 @@ pub enum DeviceCommands {
         has_sms: Option<bool>,
 
         #[arg(long)]
-        data_binary: Option<String>
+        data_binary: Option<String>,
     },
 
+    /// Register this machine as a device, reusing the device with the same nickname, and save it as the source of the pushes of the selected profile.
+    Register(RegisterArgs),
+
     /// Delete a device.
     Delete {
         /// Unique identifier for this object
         iden: String,
+
+        #[command(flatten)]
+        guard: GuardArgs,
     },
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct CreateRequest {
+#[derive(Args)]
+pub struct RegisterArgs {
     /// Name to use when displaying the device
-    /// Example: "Elon Musk's iPhone"
-    nickname: Option<String>,
-
-    /// Model of the device
-    /// Example: "iPhone 5s (GSM)"
-    model: Option<String>,
-
-    /// Manufacturer of the device
-    /// Example: "Apple"
-    manufacturer: Option<String>,
-
-    /// Platform-specific push token. If you are making your own device, leave this blank and you can listen for events on the Realtime Event Stream.
-    /// Example: "production:f73be0ee7877c8c7fa69b1468cde764f"
-    push_token: Option<String>,
-
-    /// Version of the Pushbullet application installed on the device
-    /// Example: 8623
-    app_version: Option<i32>,
-
-    /// Icon to use for this device, can be an arbitrary string. Commonly used values are: "desktop", "browser", "website", "laptop", "tablet", "phone", "watch", "system"
-    /// Example: "ios"
-    icon: Option<String>,
-
-    /// true if the devices has SMS capability, currently only true for type="android" devices
-    /// Example: true
-    has_sms: Option<bool>,
+    #[arg(long)]
+    pub nickname: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct UpdateRequest {
-    /// Name to use when displaying the device
-    /// Example: "Elon Musk's iPhone"
-    nickname: Option<String>,
-
-    /// Model of the device
-    /// Example: "iPhone 5s (GSM)"
-    model: Option<String>,
-
-    /// Manufacturer of the device
-    /// Example: "Apple"
-    manufacturer: Option<String>,
-
-    /// Platform-specific push token. If you are making your own device, leave this blank and you can listen for events on the Realtime Event Stream.
-    /// Example: "production:f73be0ee7877c8c7fa69b1468cde764f"
-    push_token: Option<String>,
-
-    /// Version of the Pushbullet application installed on the device
-    /// Example: 8623
-    app_version: Option<i32>,
-
-    /// Icon to use for this device, can be an arbitrary string. Commonly used values are: "desktop", "browser", "website", "laptop", "tablet", "phone", "watch", "system"
-    /// Example: "ios"
-    icon: Option<String>,
-
-    /// true if the devices has SMS capability, currently only true for type="android" devices
-    /// Example: true
-    has_sms: Option<bool>,
+impl RegisterArgs {
+    /// Find or create the device and save its iden as source_device of profile.
+    pub fn run(&self, client: &Client, profile: &str) -> Result<Value> {
+        let mut device = None;
+        for item in client.devices().iter(Pagination::default()) {
+            let item = item?;
+            if item.active && item.nickname.as_deref() == Some(&self.nickname) {
+                device = Some(item);
+                break;
+            }
+        }
+        let device = match device {
+            Some(device) => device,
+            None => client.devices().create(&CreateRequest {
+                nickname: Some(self.nickname.clone()),
+                icon: Some(String::from("system")),
+                ..Default::default()
+            })?,
+        };
+
+        let mut config = Config::load()?;
+        config
+            .profile_mut(profile)
+            .set(Key::SourceDevice, Some(&device.iden))?;
+        config.save()?;
+        Ok(serde_json::to_value(device)?)
+    }
 }
 
-impl Request for DeviceCommands {
-    fn build_request(&self, access_token: &str) -> Result<RequestBuilder, Box<dyn Error>> {
+impl DeviceCommands {
+    /// Send the request of the command, profile being the one `pb device register` saves the device to.
+    pub fn run(&self, client: &Client, profile: &str) -> Result<Value> {
         match self {
-            DeviceCommands::List(args) => {
-                let request_builder = reqwest::blocking::Client::new()
-                    .get("https://api.pushbullet.com/v2/devices")
-                    .query(&args.to_query());
-                Ok(request_builder)
-            }
+            DeviceCommands::List(args) if args.all => collect_pages(
+                "devices",
+                client.devices().iter(args.to_request()),
+                args.max_items,
+            ),
+            DeviceCommands::List(args) => Ok(serde_json::to_value(
+                client.devices().list(&args.to_request())?,
+            )?),
             DeviceCommands::Create {
                 nickname,
                 model,
@@ -171,26 +161,18 @@ impl Request for DeviceCommands {
                 data_binary,
             } => {
                 let request = match data_binary {
-                    Some(data_binary) => match serde_json::from_str(&data_binary) {
-                        Ok(request) => request,
-                        Err(error) => {
-                            return Err(Box::new(error));
-                        },
-                    },
+                    Some(data_binary) => serde_json::from_str(data_binary)?,
                     None => CreateRequest {
                         nickname: nickname.clone(),
                         model: model.clone(),
                         manufacturer: manufacturer.clone(),
                         push_token: push_token.clone(),
-                        app_version: app_version.clone(),
+                        app_version: *app_version,
                         icon: icon.clone(),
-                        has_sms: has_sms.clone(),
-                    }
+                        has_sms: *has_sms,
+                    },
                 };
-                let request_builder = reqwest::blocking::Client::new()
-                    .post("https://api.pushbullet.com/v2/devices")
-                    .json(&request);
-                Ok(request_builder)
+                Ok(serde_json::to_value(client.devices().create(&request)?)?)
             }
             DeviceCommands::Update {
                 iden,
@@ -204,32 +186,32 @@ impl Request for DeviceCommands {
                 data_binary,
             } => {
                 let request = match data_binary {
-                    Some(data_binary) => match serde_json::from_str(&data_binary) {
-                        Ok(request) => request,
-                        Err(error) => {
-                            return Err(Box::new(error));
-                        },
-                    },
+                    Some(data_binary) => serde_json::from_str(data_binary)?,
                     None => UpdateRequest {
                         nickname: nickname.clone(),
                         model: model.clone(),
                         manufacturer: manufacturer.clone(),
                         push_token: push_token.clone(),
-                        app_version: app_version.clone(),
+                        app_version: *app_version,
                         icon: icon.clone(),
-                        has_sms: has_sms.clone(),
-                    }
+                        has_sms: *has_sms,
+                    },
                 };
-                let request_builder = reqwest::blocking::Client::new()
-                    .post(format!("https://api.pushbullet.com/v2/devices/{}", iden))
-                    .json(&request);
-                Ok(request_builder)
+                Ok(serde_json::to_value(
+                    client.devices().update(iden, &request)?,
+                )?)
             }
-            DeviceCommands::Delete { iden } => {
-                let request_builder = reqwest::blocking::Client::new()
-                    .delete(format!("https://api.pushbullet.com/v2/devices/{}", iden));
-                Ok(request_builder)
+            DeviceCommands::Delete { iden, guard } => {
+                let device = find(
+                    Kind::Device,
+                    client.devices().iter(Pagination::default()),
+                    iden,
+                )?;
+                guard.delete(client, Kind::Device, vec![device], |iden| {
+                    client.devices().delete(iden)
+                })
             }
+            DeviceCommands::Register(register_args) => register_args.run(client, profile),
         }
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,135 @@
+use std::{path::Path, sync::Arc};
+
+use clap::Args;
+use indicatif::MultiProgress;
+use pushbullet_rust::{
+    push::{ListRequest, Push},
+    Client, DownloadOptions, PbError, Result,
+};
+use serde_json::{json, Value};
+
+use super::{guard::find, journal::Kind, progress_bar, progress_bars, time::parse_time};
+
+#[derive(Args)]
+pub struct DownloadArgs {
+    /// Iden of the file push to download.
+    #[arg(required_unless_present = "all", conflicts_with = "all")]
+    pub iden: Option<String>,
+
+    /// Download the files of every active file push.
+    #[arg(long)]
+    pub all: bool,
+
+    /// With --all, only pushes created since this time: a unix timestamp, a duration ago such as 12h or 7d, or a UTC date such as 2024-05-01T08:30.
+    #[arg(long, value_name = "TIME", requires = "all", value_parser = parse_time)]
+    pub since: Option<f64>,
+
+    /// Directory to save the files in, created when missing. Existing files are never overwritten, a number is added to the name instead.
+    #[arg(long, default_value = ".")]
+    pub dir: String,
+
+    /// Fail when the size of a downloaded file differs from the size announced by the server.
+    #[arg(long)]
+    pub verify_size: bool,
+
+    /// Don't show the download progress bar.
+    #[arg(long)]
+    pub quiet: bool,
+}
+
+impl DownloadArgs {
+    pub fn request(&self, client: &Client) -> Result<Value> {
+        let bars = progress_bars(self.quiet);
+        let Some(iden) = &self.iden else {
+            return self.download_all(client, &bars);
+        };
+        let request = ListRequest {
+            active: Some(true),
+            limit: Some(500),
+            ..Default::default()
+        };
+        let push = find(Kind::Push, client.pushes().iter(request), iden)?;
+        let push = serde_json::from_value(push)?;
+        let file = self.download(client, &push, &bars)?;
+        Ok(json!({ "files": [file] }))
+    }
+
+    fn download_all(&self, client: &Client, bars: &MultiProgress) -> Result<Value> {
+        let request = ListRequest {
+            modified_after: self.since.map(|since| since.to_string()),
+            active: Some(true),
+            cursor: None,
+            limit: Some(500),
+        };
+        let mut files = vec![];
+        let mut failed = 0;
+        for push in client.pushes().iter(request) {
+            let push = push?;
+            // Pushes modified since, such as dismissed ones, may have been created long before.
+            if push.t.as_deref() != Some("file")
+                || push.file_url.is_none()
+                || self.since.is_some_and(|since| push.created < since)
+            {
+                continue;
+            }
+            match self.download(client, &push, bars) {
+                Ok(file) => files.push(file),
+                Err(e) => {
+                    failed += 1;
+                    eprintln!(
+                        "Failed to download {}: {e}",
+                        push.file_name.as_deref().unwrap_or(&push.iden)
+                    );
+                }
+            }
+        }
+        if failed > 0 {
+            let mut message = format!(
+                "{failed} of {} files failed, {} downloaded",
+                files.len() + failed,
+                files.len()
+            );
+            let paths: Vec<&str> = files
+                .iter()
+                .filter_map(|file| file["path"].as_str())
+                .collect();
+            if !paths.is_empty() {
+                message.push_str(&format!(": {}", paths.join(", ")));
+            }
+            return Err(PbError::Io(message));
+        }
+        eprintln!("Downloaded {} files", files.len());
+        Ok(json!({ "files": files }))
+    }
+
+    fn download(&self, client: &Client, push: &Push, bars: &MultiProgress) -> Result<Value> {
+        let file_url = push
+            .file_url
+            .as_deref()
+            .ok_or_else(|| PbError::Io(format!("push {} has no file", push.iden)))?;
+        let file_name = push.file_name.as_deref().unwrap_or("download");
+
+        let bar = progress_bar(bars, file_name);
+        let progress = bar.clone();
+        let options = DownloadOptions {
+            verify_size: self.verify_size,
+            progress: Some(Arc::new(move |received, size| {
+                progress.set_length(size);
+                progress.set_position(received);
+            })),
+            ..Default::default()
+        };
+        let path = client.download_file(file_url, Path::new(&self.dir), file_name, &options);
+        bar.finish_and_clear();
+        bars.remove(&bar);
+        let path = path?;
+
+        let size = path.metadata().map(|metadata| metadata.len()).ok();
+        Ok(json!({
+            "iden": push.iden,
+            "file_name": file_name,
+            "path": path.to_string_lossy(),
+            "size": size,
+        }))
+    }
+}
@@ -0,0 +1,143 @@
+use std::{env, io};
+
+use clap::{Args, Subcommand};
+use pushbullet_rust::{
+    ephemeral::{EncryptionKey, EphemeralPush},
+    Client, PbError, Result,
+};
+use serde_json::Value;
+
+use super::Request;
+
+#[derive(Subcommand)]
+pub enum EphemeralCommands {
+    /// Copy text to the clipboard of the devices with universal copy/paste enabled.
+    Clip {
+        /// Text to copy, read from stdin when not given.
+        body: Option<String>,
+
+        /// Device the clip comes from, by default the source_device of the profile.
+        #[arg(long)]
+        source_device_iden: Option<String>,
+
+        #[command(flatten)]
+        encryption: EncryptionArgs,
+    },
+
+    /// Dismiss a notification mirrored from an Android device on the other devices.
+    Dismiss {
+        /// Package name of the app that posted the notification
+        #[arg(long)]
+        package_name: String,
+
+        /// Android id of the notification
+        #[arg(long)]
+        notification_id: String,
+
+        /// Android tag of the notification
+        #[arg(long)]
+        notification_tag: Option<String>,
+
+        #[command(flatten)]
+        encryption: EncryptionArgs,
+    },
+
+    /// Send an ephemeral push given as JSON.
+    Send {
+        #[arg(long)]
+        data_binary: String,
+
+        #[command(flatten)]
+        encryption: EncryptionArgs,
+    },
+}
+
+#[derive(Args)]
+pub struct EncryptionArgs {
+    /// End-to-end encrypt with the password set in the Pushbullet apps, read from PB_ENCRYPTION_PASSWORD or asked on the terminal.
+    #[arg(long)]
+    pub encrypt: bool,
+}
+
+impl EncryptionArgs {
+    pub fn key(&self, user_iden: &str) -> Result<Option<EncryptionKey>> {
+        self.encrypt.then(|| encryption_key(user_iden)).transpose()
+    }
+}
+
+impl EphemeralCommands {
+    /// Send clips from device when no source device is given.
+    pub fn set_default_source(&mut self, device: &str) {
+        if let EphemeralCommands::Clip {
+            source_device_iden: source_device_iden @ None,
+            ..
+        } = self
+        {
+            *source_device_iden = Some(device.to_owned());
+        }
+    }
+}
+
+impl Request for EphemeralCommands {
+    fn request(&self, client: &Client) -> Result<Value> {
+        let ephemerals = client.ephemerals();
+        match self {
+            EphemeralCommands::Clip {
+                body,
+                source_device_iden,
+                encryption,
+            } => {
+                let body = match body {
+                    Some(body) => body.clone(),
+                    None => io::read_to_string(io::stdin())
+                        .map_err(|e| PbError::Io(format!("stdin: {e}")))?,
+                };
+                let user = client.users().me()?;
+                let key = encryption.key(&user.iden)?;
+                let push = EphemeralPush::Clip {
+                    body,
+                    source_user_iden: user.iden,
+                    source_device_iden: source_device_iden.clone(),
+                };
+                ephemerals.send(&push, key.as_ref())
+            }
+            EphemeralCommands::Dismiss {
+                package_name,
+                notification_id,
+                notification_tag,
+                encryption,
+            } => {
+                let user = client.users().me()?;
+                let key = encryption.key(&user.iden)?;
+                let push = EphemeralPush::Dismissal {
+                    package_name: package_name.clone(),
+                    notification_id: notification_id.clone(),
+                    notification_tag: notification_tag.clone(),
+                    source_user_iden: user.iden,
+                };
+                ephemerals.send(&push, key.as_ref())
+            }
+            EphemeralCommands::Send {
+                data_binary,
+                encryption,
+            } => {
+                let push: Value = serde_json::from_str(data_binary)?;
+                let key = match encryption.encrypt {
+                    true => encryption.key(&client.users().me()?.iden)?,
+                    false => None,
+                };
+                ephemerals.send(&push, key.as_ref())
+            }
+        }
+    }
+}
+
+/// Key derived from the encryption password and the iden of the current user.
+pub fn encryption_key(user_iden: &str) -> Result<EncryptionKey> {
+    let password = match env::var("PB_ENCRYPTION_PASSWORD") {
+        Ok(password) => password,
+        Err(_) => rpassword::prompt_password("Encryption password: ")
+            .map_err(|e| PbError::Config(format!("encryption password: {e}")))?,
+    };
+    Ok(EncryptionKey::derive(&password, user_iden))
+}
@@ -0,0 +1,171 @@
+use std::{
+    collections::VecDeque,
+    io::{self, BufRead, BufReader, Read, Write},
+    process::{self, ExitStatus, Stdio},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use clap::Args;
+use pushbullet_rust::{push::CreateRequest, Client, PbError, Result};
+
+use super::TargetArgs;
+
+#[derive(Args)]
+pub struct ExecArgs {
+    /// Title of the push, by default the command and whether it succeeded.
+    #[arg(long)]
+    pub title: Option<String>,
+
+    /// Only send a push when the command fails.
+    #[arg(long)]
+    pub on_failure: bool,
+
+    /// Only send a push when the command ran for at least this many seconds.
+    #[arg(long, value_name = "SECONDS", default_value = "0")]
+    pub min_duration: u64,
+
+    /// Number of last lines of stdout and of stderr sent in the push.
+    #[arg(long, default_value = "10")]
+    pub lines: usize,
+
+    #[command(flatten)]
+    pub target: TargetArgs,
+
+    /// Command to run and its arguments, after --.
+    #[arg(required = true, last = true)]
+    pub command: Vec<String>,
+}
+
+impl ExecArgs {
+    /// Run the command with its output passed through, then push its status, runtime and last lines of output. Returns the exit code of the command, also when the push fails.
+    pub fn run(&self, client: &Client) -> Result<i32> {
+        let command_line = self.command.join(" ");
+        let started = Instant::now();
+        let guid = format!(
+            "pb-exec-{}-{}",
+            process::id(),
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis()
+        );
+
+        let mut child = process::Command::new(&self.command[0])
+            .args(&self.command[1..])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| PbError::Io(format!("{}: {e}", self.command[0])))?;
+        let stdout = forward(child.stdout.take().unwrap(), io::stdout(), self.lines);
+        let stderr = forward(child.stderr.take().unwrap(), io::stderr(), self.lines);
+        let status = child
+            .wait()
+            .map_err(|e| PbError::Io(format!("{}: {e}", self.command[0])))?;
+        let stdout = stdout.join().unwrap_or_default();
+        let stderr = stderr.join().unwrap_or_default();
+        let runtime = started.elapsed();
+
+        let code = exit_code(status);
+        if (self.on_failure && code == 0) || runtime < Duration::from_secs(self.min_duration) {
+            return Ok(code);
+        }
+
+        let outcome = match code {
+            0 => String::from("succeeded"),
+            _ => format!("failed ({})", describe(status)),
+        };
+        let mut body = format!(
+            "Status: {}\nRuntime: {}",
+            describe(status),
+            format_duration(runtime)
+        );
+        for (name, lines) in [("stdout", stdout), ("stderr", stderr)] {
+            if !lines.is_empty() {
+                let lines: Vec<String> = lines.into();
+                body.push_str(&format!("\n\n{name}:\n{}", lines.join("\n")));
+            }
+        }
+
+        let request = CreateRequest {
+            t: Some(String::from("note")),
+            title: Some(
+                self.title
+                    .clone()
+                    .unwrap_or_else(|| format!("{command_line} {outcome}")),
+            ),
+            body: Some(body),
+            guid: Some(guid),
+            ..self.target.to_request()
+        };
+        if let Err(error) = client.pushes().create(&request) {
+            eprintln!("Failed to send push: {error}");
+        }
+        Ok(code)
+    }
+}
+
+/// Copy reader to writer as it comes, keeping its last lines.
+fn forward<R, W>(reader: R, mut writer: W, lines: usize) -> JoinHandle<VecDeque<String>>
+where
+    R: Read + Send + 'static,
+    W: Write + Send + 'static,
+{
+    thread::spawn(move || {
+        let mut reader = BufReader::new(reader);
+        let mut last = VecDeque::with_capacity(lines);
+        let mut line = vec![];
+        while reader.read_until(b'\n', &mut line).unwrap_or(0) > 0 {
+            let _ = writer.write_all(&line).and_then(|_| writer.flush());
+            if lines > 0 {
+                if last.len() == lines {
+                    last.pop_front();
+                }
+                last.push_back(String::from_utf8_lossy(&line).trim_end().to_owned());
+            }
+            line.clear();
+        }
+        last
+    })
+}
+
+/// Exit code to exit with, 128 plus the signal for a command killed by a signal as shells do.
+fn exit_code(status: ExitStatus) -> i32 {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        if let Some(signal) = status.signal() {
+            return 128 + signal;
+        }
+    }
+    status.code().unwrap_or(1)
+}
+
+fn describe(status: ExitStatus) -> String {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        if let Some(signal) = status.signal() {
+            return format!("killed by signal {signal}");
+        }
+    }
+    match status.code() {
+        Some(code) => format!("exit code {code}"),
+        None => String::from("unknown"),
+    }
+}
+
+/// Runtime such as 1h 2m 3s, or 4.2s under a minute.
+fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    match seconds {
+        0..60 => format!("{:.1}s", duration.as_secs_f64()),
+        60..3600 => format!("{}m {}s", seconds / 60, seconds % 60),
+        _ => format!(
+            "{}h {}m {}s",
+            seconds / 3600,
+            seconds % 3600 / 60,
+            seconds % 60
+        ),
+    }
+}
@@ -0,0 +1,237 @@
+use std::{
+    fs::{self, File},
+    io,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    thread,
+};
+
+use indicatif::MultiProgress;
+use pushbullet_rust::{
+    push::{CreateRequest, Push},
+    Client, PbError, Result,
+};
+use serde_json::{json, Value};
+use tempfile::TempDir;
+use zip::{write::SimpleFileOptions, CompressionMethod, ZipWriter};
+
+use super::upload;
+
+/// Local files to send, keeping the temporary archive of --zip alive until they are uploaded.
+pub struct Files {
+    pub paths: Vec<String>,
+    _archive: Option<TempDir>,
+}
+
+impl Files {
+    /// Files named by file_names, expanding globs, and the files under dir, archived into a single zip file when zip is set.
+    pub fn collect(file_names: &[String], dir: Option<&str>, zip: bool) -> Result<Files> {
+        let mut paths = vec![];
+        for file_name in file_names {
+            paths.extend(expand(file_name)?);
+        }
+        let mut archive = None;
+        if let Some(dir) = dir {
+            let io_error = |e: io::Error| PbError::Io(format!("{dir}: {e}"));
+            if zip {
+                let (temp_dir, path) = zip_dir(Path::new(dir)).map_err(io_error)?;
+                paths.push(path);
+                archive = Some(temp_dir);
+            } else {
+                let mut files = vec![];
+                walk(Path::new(dir), &mut files).map_err(io_error)?;
+                paths.extend(files.iter().map(|file| file.to_string_lossy().into_owned()));
+            }
+        }
+        if paths.is_empty() {
+            return Err(PbError::Io(String::from("no files to send")));
+        }
+        Ok(Files {
+            paths,
+            _archive: archive,
+        })
+    }
+}
+
+/// A MIME type given for the files matching a glob, as PATTERN=TYPE.
+#[derive(Clone)]
+pub struct TypeOverride {
+    pattern: glob::Pattern,
+    file_type: String,
+}
+
+impl FromStr for TypeOverride {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<TypeOverride, String> {
+        let (pattern, file_type) = s
+            .rsplit_once('=')
+            .filter(|(pattern, file_type)| !pattern.is_empty() && file_type.contains('/'))
+            .ok_or_else(|| format!("expected PATTERN=TYPE such as '*.log=text/plain', got {s}"))?;
+        Ok(TypeOverride {
+            pattern: glob::Pattern::new(pattern).map_err(|e| e.to_string())?,
+            file_type: file_type.to_owned(),
+        })
+    }
+}
+
+/// MIME types given on the command line, the rest being detected when uploading.
+#[derive(Default)]
+pub struct FileTypes {
+    pub file_type: Option<String>,
+    pub overrides: Vec<TypeOverride>,
+}
+
+impl FileTypes {
+    /// Type of the first override matching the path or its file name, otherwise the type given for every file.
+    pub fn get(&self, path: &str) -> Option<String> {
+        let file_name = Path::new(path)
+            .file_name()
+            .map(|name| name.to_string_lossy())
+            .unwrap_or_default();
+        self.overrides
+            .iter()
+            .find(|o| o.pattern.matches(path) || o.pattern.matches(&file_name))
+            .map(|o| o.file_type.clone())
+            .or_else(|| self.file_type.clone())
+    }
+}
+
+/// Paths matching a glob, or the file name itself when it has no glob characters.
+fn expand(file_name: &str) -> Result<Vec<String>> {
+    if !file_name.contains(['*', '?', '[']) {
+        return Ok(vec![file_name.to_owned()]);
+    }
+    let glob_error = |e: String| PbError::Io(format!("{file_name}: {e}"));
+    let mut paths = vec![];
+    for path in glob::glob(file_name).map_err(|e| glob_error(e.to_string()))? {
+        let path = path.map_err(|e| glob_error(e.to_string()))?;
+        if path.is_file() {
+            paths.push(path.to_string_lossy().into_owned());
+        }
+    }
+    if paths.is_empty() {
+        return Err(glob_error(String::from("no matching files")));
+    }
+    Ok(paths)
+}
+
+/// Every file under dir, sorted so the pushes come in a stable order.
+fn walk(dir: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    let mut entries: Vec<PathBuf> = fs::read_dir(dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<io::Result<_>>()?;
+    entries.sort();
+    for path in entries {
+        if path.is_dir() {
+            walk(&path, files)?;
+        } else if path.is_file() {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Archive dir into <dir name>.zip in a temporary directory.
+fn zip_dir(dir: &Path) -> io::Result<(TempDir, String)> {
+    let name = dir
+        .canonicalize()?
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| String::from("archive"));
+    let temp_dir = TempDir::new()?;
+    let path = temp_dir.path().join(format!("{name}.zip"));
+
+    let mut files = vec![];
+    walk(dir, &mut files)?;
+    let mut writer = ZipWriter::new(File::create(&path)?);
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    for file in files {
+        let name = file.strip_prefix(dir).unwrap_or(&file);
+        writer.start_file(name.to_string_lossy(), options)?;
+        io::copy(&mut File::open(&file)?, &mut writer)?;
+    }
+    writer.finish()?;
+    Ok((temp_dir, path.to_string_lossy().into_owned()))
+}
+
+/// Upload every file and create one push each from request, jobs files at a time. Prints a summary on stderr and fails when any file failed, naming the pushes created so they are not sent twice.
+pub fn send_all(
+    client: &Client,
+    request: &CreateRequest,
+    paths: &[String],
+    types: &FileTypes,
+    jobs: usize,
+    bars: &MultiProgress,
+) -> Result<Value> {
+    let next = AtomicUsize::new(0);
+    let results: Mutex<Vec<Option<Result<Push>>>> =
+        Mutex::new(paths.iter().map(|_| None).collect());
+
+    thread::scope(|scope| {
+        for _ in 0..jobs.clamp(1, paths.len()) {
+            scope.spawn(|| loop {
+                let i = next.fetch_add(1, Ordering::SeqCst);
+                let Some(path) = paths.get(i) else {
+                    break;
+                };
+                let result = send_file(client, request, i, path, types, bars);
+                results.lock().unwrap()[i] = Some(result);
+            });
+        }
+    });
+
+    let mut pushes = vec![];
+    let mut sent = vec![];
+    let mut failed = 0;
+    for (path, result) in paths.iter().zip(results.into_inner().unwrap()) {
+        match result.unwrap() {
+            Ok(push) => {
+                sent.push(format!("{path} ({})", push.iden));
+                pushes.push(push);
+            }
+            Err(e) => {
+                failed += 1;
+                eprintln!("Failed to send {path}: {e}");
+            }
+        }
+    }
+    if failed > 0 {
+        let mut message = format!(
+            "{failed} of {} files failed, {} sent",
+            paths.len(),
+            sent.len()
+        );
+        if !sent.is_empty() {
+            message.push_str(&format!(": {}", sent.join(", ")));
+        }
+        return Err(PbError::Upload(message));
+    }
+    eprintln!("Sent {} files", pushes.len());
+    Ok(json!({ "pushes": pushes }))
+}
+
+fn send_file(
+    client: &Client,
+    request: &CreateRequest,
+    i: usize,
+    path: &str,
+    types: &FileTypes,
+    bars: &MultiProgress,
+) -> Result<Push> {
+    let response = upload(client, path, types.get(path), bars)?;
+    let request = CreateRequest {
+        t: Some(String::from("file")),
+        file_name: Some(response.file_name),
+        file_type: Some(response.file_type),
+        file_url: Some(response.file_url),
+        // The same guid on every push would make the server keep only the first one.
+        guid: request.guid.as_ref().map(|guid| format!("{guid}-{i}")),
+        ..request.clone()
+    };
+    client.pushes().create(&request)
+}
@@ -0,0 +1,229 @@
+use std::io::{self, BufRead, IsTerminal, Write};
+
+use clap::Args;
+use pushbullet_rust::{error::ApiError, Client, Page, Paginator, PbError, Result};
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use super::journal::{Journal, Kind};
+
+/// Safety options of commands deleting objects.
+#[derive(Args, Clone)]
+pub struct GuardArgs {
+    /// Delete without asking for confirmation, needed when stdin is not a terminal.
+    #[arg(long, short)]
+    pub yes: bool,
+
+    /// Print the objects and the HTTP requests that would delete them without sending anything.
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Profile whose undo journal records the deleted objects, set by main.
+    #[arg(skip)]
+    pub profile: String,
+}
+
+impl GuardArgs {
+    /// Delete objects of kind one by one with delete once confirmed, recording them in the undo journal. Goes on after failures and returns the last one.
+    pub fn delete<F>(
+        &self,
+        client: &Client,
+        kind: Kind,
+        objects: Vec<Value>,
+        delete: F,
+    ) -> Result<Value>
+    where
+        F: Fn(&str) -> Result<()>,
+    {
+        let urls = objects
+            .iter()
+            .map(|object| {
+                let iden = object["iden"].as_str().unwrap_or_default();
+                format!("{}/{}/{iden}", client.api_url(), kind.path())
+            })
+            .collect();
+        if let Some(res) = self.check(kind, &objects, urls)? {
+            return Ok(res);
+        }
+
+        let journal = Journal::open(&self.profile)?;
+        let total = objects.len();
+        let mut deleted = vec![];
+        let mut last_error = None;
+        for object in objects {
+            let iden = object["iden"].as_str().unwrap_or_default();
+            match delete(iden) {
+                Ok(()) => {
+                    journal.record(kind, &object)?;
+                    deleted.push(object);
+                }
+                Err(e) => {
+                    eprintln!("Failed to delete {} {iden}: {e}", kind.name());
+                    last_error = Some(e);
+                }
+            }
+        }
+        if let Some(e) = last_error {
+            eprintln!(
+                "{} of {total} {} failed, {} deleted",
+                total - deleted.len(),
+                kind.path(),
+                deleted.len()
+            );
+            return Err(e);
+        }
+        eprintln!("Deleted {}", describe_all(kind, &deleted));
+        Ok(json!({ kind.path(): deleted }))
+    }
+
+    /// Delete every object of kind with a single request once confirmed, recording them in the undo journal.
+    pub fn delete_all<F>(
+        &self,
+        client: &Client,
+        kind: Kind,
+        objects: Vec<Value>,
+        delete: F,
+    ) -> Result<Value>
+    where
+        F: FnOnce() -> Result<()>,
+    {
+        let url = format!("{}/{}", client.api_url(), kind.path());
+        if let Some(res) = self.check(kind, &objects, vec![url])? {
+            return Ok(res);
+        }
+        delete()?;
+        let journal = Journal::open(&self.profile)?;
+        for object in &objects {
+            journal.record(kind, object)?;
+        }
+        eprintln!("Deleted {}", describe_all(kind, &objects));
+        Ok(json!({ kind.path(): objects }))
+    }
+
+    /// What to print instead of deleting: the requests with --dry-run, nothing when there is nothing to delete or the deletion is not confirmed.
+    fn check(&self, kind: Kind, objects: &[Value], urls: Vec<String>) -> Result<Option<Value>> {
+        if objects.is_empty() {
+            eprintln!("No {} to delete", kind.path());
+            return Ok(Some(json!({ kind.path(): objects })));
+        }
+        if self.dry_run {
+            let requests: Vec<Value> = urls
+                .into_iter()
+                .map(|url| json!({ "method": "DELETE", "url": url }))
+                .collect();
+            return Ok(Some(json!({ kind.path(): objects, "requests": requests })));
+        }
+        if !self.confirm(&format!("Delete {}", describe_all(kind, objects)))? {
+            eprintln!("Cancelled");
+            return Ok(Some(json!({})));
+        }
+        Ok(None)
+    }
+
+    /// Whether to go on: true with --yes, otherwise asks on the terminal, refusing when there is none.
+    pub fn confirm(&self, question: &str) -> Result<bool> {
+        if self.yes {
+            return Ok(true);
+        }
+        if !io::stdin().is_terminal() {
+            return Err(PbError::Config(format!(
+                "{question}? refusing without a terminal to confirm, pass --yes"
+            )));
+        }
+        eprint!("{question}? [y/N] ");
+        let _ = io::stderr().flush();
+        let mut answer = String::new();
+        io::stdin()
+            .lock()
+            .read_line(&mut answer)
+            .map_err(|e| PbError::Io(format!("stdin: {e}")))?;
+        Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
+    }
+}
+
+/// The object with iden among the objects listed by paginator, the API having no endpoint to get a single one.
+pub fn find<P>(kind: Kind, paginator: Paginator<'_, P>, iden: &str) -> Result<Value>
+where
+    P: Page,
+    P::Item: Serialize,
+{
+    let mut objects = find_all(kind, paginator, &[iden.to_owned()])?;
+    Ok(objects.remove(0))
+}
+
+/// The objects with idens, in their order, listing the objects with paginator only until every one is found.
+pub fn find_all<P>(kind: Kind, paginator: Paginator<'_, P>, idens: &[String]) -> Result<Vec<Value>>
+where
+    P: Page,
+    P::Item: Serialize,
+{
+    let mut found: Vec<Option<Value>> = vec![None; idens.len()];
+    for item in paginator {
+        let item = serde_json::to_value(item?)?;
+        for (iden, slot) in idens.iter().zip(&mut found) {
+            if slot.is_none() && item["iden"] == iden.as_str() {
+                *slot = Some(item.clone());
+            }
+        }
+        if found.iter().all(Option::is_some) {
+            break;
+        }
+    }
+    match idens
+        .iter()
+        .zip(&found)
+        .find(|(_, object)| object.is_none())
+    {
+        Some((iden, _)) => Err(not_found(kind, iden)),
+        None => Ok(found.into_iter().flatten().collect()),
+    }
+}
+
+fn not_found(kind: Kind, iden: &str) -> PbError {
+    PbError::Api {
+        status: 404,
+        error: Some(ApiError {
+            code: None,
+            t: None,
+            message: Some(format!("no {} with iden {iden}", kind.name())),
+            cite: None,
+        }),
+    }
+}
+
+/// Such as: device "Pixel 7" (ujpah72o0), or 12 pushes: "a" (x), "b" (y)... and 2 more.
+pub fn describe_all(kind: Kind, objects: &[Value]) -> String {
+    const SHOWN: usize = 10;
+    if let [object] = objects {
+        return format!("{} {}", kind.name(), describe(object));
+    }
+    let mut description = format!("{} {}", objects.len(), kind.path());
+    for (i, object) in objects.iter().take(SHOWN).enumerate() {
+        description.push_str(if i == 0 { ": " } else { ", " });
+        description.push_str(&describe(object));
+    }
+    if objects.len() > SHOWN {
+        description.push_str(&format!("... and {} more", objects.len() - SHOWN));
+    }
+    description
+}
+
+/// The name of an object and its iden.
+fn describe(object: &Value) -> String {
+    let iden = object["iden"].as_str().unwrap_or_default();
+    let name = [
+        &object["title"],
+        &object["file_name"],
+        &object["url"],
+        &object["body"],
+        &object["nickname"],
+        &object["with"]["email"],
+        &object["channel"]["tag"],
+    ]
+    .into_iter()
+    .find_map(|name| name.as_str());
+    match name {
+        Some(name) => format!("{:?} ({iden})", name.lines().next().unwrap_or_default()),
+        None => iden.to_owned(),
+    }
+}
@@ -0,0 +1,240 @@
+use std::{
+    env,
+    fs::{self, OpenOptions},
+    io::{ErrorKind, Write},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use clap::Args;
+use pushbullet_rust::{chat, device, push, subscription, Client, PbError, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use super::{
+    config::{check_profile, write_private},
+    time::now,
+};
+
+/// Kind of object deleted by a command.
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Kind {
+    Push,
+    Device,
+    Chat,
+    Subscription,
+    Text,
+}
+
+impl Kind {
+    pub fn name(self) -> &'static str {
+        match self {
+            Kind::Push => "push",
+            Kind::Device => "device",
+            Kind::Chat => "chat",
+            Kind::Subscription => "subscription",
+            Kind::Text => "text",
+        }
+    }
+
+    /// Whether deleted objects of this kind can be created again, texts being already sent or canceled.
+    pub fn restorable(self) -> bool {
+        self != Kind::Text
+    }
+
+    /// Path of the API endpoint, also the plural of the name.
+    pub fn path(self) -> &'static str {
+        match self {
+            Kind::Push => "pushes",
+            Kind::Device => "devices",
+            Kind::Chat => "chats",
+            Kind::Subscription => "subscriptions",
+            Kind::Text => "texts",
+        }
+    }
+}
+
+/// A deleted object, deleted along with the others of its batch by a single command.
+#[derive(Serialize, Deserialize)]
+struct Entry {
+    batch: String,
+    time: f64,
+    kind: Kind,
+    object: Value,
+    #[serde(default)]
+    undone: bool,
+}
+
+/// Objects deleted through pb, one JSON line each in ~/.local/share/pbr/<profile>/journal.jsonl, so pb undo can create them again.
+pub struct Journal {
+    path: PathBuf,
+    batch: String,
+}
+
+impl Journal {
+    pub fn open(profile: &str) -> Result<Journal> {
+        let home = env::var("HOME").map_err(|e| PbError::Config(format!("HOME: {e}")))?;
+        let path = Path::new(&home)
+            .join(".local")
+            .join("share")
+            .join("pbr")
+            .join(check_profile(profile)?)
+            .join("journal.jsonl");
+        let batch = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis()
+            .to_string();
+        Ok(Journal { path, batch })
+    }
+
+    /// Append a deleted object to the journal, unless it could never be created again.
+    pub fn record(&self, kind: Kind, object: &Value) -> Result<()> {
+        if !kind.restorable() {
+            return Ok(());
+        }
+        let entry = Entry {
+            batch: self.batch.clone(),
+            time: now(),
+            kind,
+            object: object.clone(),
+            undone: false,
+        };
+        let io_error = |e: std::io::Error| PbError::Io(format!("{}: {e}", self.path.display()));
+        fs::create_dir_all(self.path.parent().unwrap()).map_err(io_error)?;
+        let mut options = OpenOptions::new();
+        options.append(true).create(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            // Deleted pushes are as private as the access token.
+            options.mode(0o600);
+        }
+        let line = serde_json::to_string(&entry)? + "\n";
+        options
+            .open(&self.path)
+            .and_then(|mut file| file.write_all(line.as_bytes()))
+            .map_err(io_error)
+    }
+
+    fn entries(&self) -> Result<Vec<Entry>> {
+        let journal_error = |e: String| PbError::Io(format!("{}: {e}", self.path.display()));
+        let text = match fs::read_to_string(&self.path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(vec![]),
+            Err(e) => return Err(journal_error(e.to_string())),
+        };
+        text.lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| serde_json::from_str(line).map_err(|e| journal_error(e.to_string())))
+            .collect()
+    }
+
+    fn save(&self, entries: &[Entry]) -> Result<()> {
+        let mut text = String::new();
+        for entry in entries {
+            text.push_str(&serde_json::to_string(entry)?);
+            text.push('\n');
+        }
+        write_private(&self.path, text.as_bytes())
+            .map_err(|e| PbError::Io(format!("{}: {e}", self.path.display())))
+    }
+}
+
+#[derive(Args)]
+pub struct UndoArgs {
+    /// Print the journal of deleted objects instead of undoing anything.
+    #[arg(long)]
+    pub list: bool,
+
+    /// Batch to undo, as printed by --list, by default the last one not undone yet.
+    #[arg(long)]
+    pub batch: Option<String>,
+}
+
+impl UndoArgs {
+    /// Create the objects of a batch of the journal again. They get new idens, and texts, which cannot be created again since they were already sent or canceled, are skipped.
+    pub fn run(&self, client: &Client, profile: &str) -> Result<Value> {
+        let journal = Journal::open(profile)?;
+        let mut entries = journal.entries()?;
+        if self.list {
+            return Ok(json!({ "deleted": entries }));
+        }
+
+        let batch = match &self.batch {
+            Some(batch) => batch.clone(),
+            None => entries
+                .iter()
+                .rev()
+                .find(|entry| !entry.undone && entry.kind.restorable())
+                .map(|entry| entry.batch.clone())
+                .ok_or_else(|| PbError::Config(String::from("nothing to undo")))?,
+        };
+        let mut created = vec![];
+        let mut last_error = None;
+        for entry in entries
+            .iter_mut()
+            .filter(|entry| entry.batch == batch && !entry.undone && entry.kind.restorable())
+        {
+            match restore(client, entry.kind, &entry.object) {
+                Ok(object) => {
+                    entry.undone = true;
+                    created.push(object);
+                }
+                Err(e) => {
+                    eprintln!(
+                        "Failed to create {} {} again: {e}",
+                        entry.kind.name(),
+                        entry.object["iden"].as_str().unwrap_or_default()
+                    );
+                    last_error = Some(e);
+                }
+            }
+        }
+        journal.save(&entries)?;
+        match last_error {
+            Some(e) => Err(e),
+            None => Ok(json!({ "created": created })),
+        }
+    }
+}
+
+/// Create a deleted object again from its fields.
+fn restore(client: &Client, kind: Kind, object: &Value) -> Result<Value> {
+    let created = match kind {
+        Kind::Push => {
+            let mut request: push::CreateRequest = serde_json::from_value(object.clone())?;
+            request.device_iden = object["target_device_iden"].as_str().map(str::to_owned);
+            if object["direction"] == "outgoing" {
+                request.email = object["receiver_email"].as_str().map(str::to_owned);
+            }
+            // The server would answer with the deleted push for the same guid.
+            request.guid = None;
+            request.client_iden = None;
+            serde_json::to_value(client.pushes().create(&request)?)?
+        }
+        Kind::Device => {
+            let request: device::CreateRequest = serde_json::from_value(object.clone())?;
+            serde_json::to_value(client.devices().create(&request)?)?
+        }
+        Kind::Chat => {
+            let request = chat::CreateRequest {
+                email: object["with"]["email"].as_str().map(str::to_owned),
+            };
+            serde_json::to_value(client.chats().create(&request)?)?
+        }
+        Kind::Subscription => {
+            let request = subscription::CreateRequest {
+                channel_tag: object["channel"]["tag"].as_str().map(str::to_owned),
+            };
+            serde_json::to_value(client.subscriptions().create(&request)?)?
+        }
+        Kind::Text => {
+            return Err(PbError::Config(String::from(
+                "texts cannot be created again, they were already sent or canceled",
+            )))
+        }
+    };
+    Ok(created)
+}
@@ -0,0 +1,110 @@
+use std::{
+    env, fs,
+    path::PathBuf,
+    time::{Duration, SystemTime},
+};
+
+use age::secrecy::SecretString;
+use pushbullet_rust::{PbError, Result};
+
+use super::config::{check_profile, write_private, Config};
+
+/// Seconds a decrypted access token is remembered when keyring_cache is not set.
+const DEFAULT_CACHE: u64 = 900;
+
+/// Encrypted access token of a profile, stored next to the config as <profile>.age.
+pub fn path(profile: &str) -> Result<PathBuf> {
+    let config = Config::path()?;
+    Ok(config.with_file_name(format!("{}.age", check_profile(profile)?)))
+}
+
+/// Encrypt the access token with a passphrase asked twice, PB_KEYRING_PASSPHRASE being used instead when set.
+pub fn store(profile: &str, access_token: &str) -> Result<()> {
+    let passphrase = match env::var("PB_KEYRING_PASSPHRASE") {
+        Ok(passphrase) => passphrase,
+        Err(_) => {
+            let passphrase = prompt("New keyring passphrase: ")?;
+            if prompt("Repeat passphrase: ")? != passphrase {
+                return Err(keyring_error("passphrases do not match"));
+            }
+            passphrase
+        }
+    };
+    if passphrase.is_empty() {
+        return Err(keyring_error("empty passphrase"));
+    }
+
+    let recipient = age::scrypt::Recipient::new(SecretString::from(passphrase));
+    let encrypted = age::encrypt_and_armor(&recipient, access_token.as_bytes())
+        .map_err(|e| keyring_error(&e.to_string()))?;
+    let path = path(profile)?;
+    write_private(&path, encrypted.as_bytes())
+        .map_err(|e| PbError::Config(format!("{}: {e}", path.display())))?;
+    forget(profile);
+    Ok(())
+}
+
+/// Decrypt the access token of a profile, asking for the passphrase unless it was decrypted less than cache seconds ago.
+pub fn load(profile: &str, cache: Option<u64>) -> Result<String> {
+    let cache = Duration::from_secs(cache.unwrap_or(DEFAULT_CACHE));
+    if let Some(access_token) = cached(profile, cache) {
+        return Ok(access_token);
+    }
+
+    let path = path(profile)?;
+    let encrypted = fs::read(&path).map_err(|e| {
+        PbError::Config(format!(
+            "{}: {e}, store a token with `pb access-token --keyring`",
+            path.display()
+        ))
+    })?;
+    let passphrase = match env::var("PB_KEYRING_PASSPHRASE") {
+        Ok(passphrase) => passphrase,
+        Err(_) => prompt(&format!("Keyring passphrase for profile {profile}: "))?,
+    };
+    let identity = age::scrypt::Identity::new(SecretString::from(passphrase));
+    let decrypted =
+        age::decrypt(&identity, &encrypted).map_err(|e| keyring_error(&e.to_string()))?;
+    let access_token = String::from_utf8(decrypted).map_err(|e| keyring_error(&e.to_string()))?;
+
+    if !cache.is_zero() {
+        if let Some(cache_path) = cache_path(profile) {
+            // A failing cache only means asking for the passphrase again next time.
+            let _ = write_private(&cache_path, access_token.as_bytes());
+        }
+    }
+    Ok(access_token)
+}
+
+/// Remove the remembered access token of a profile.
+pub fn forget(profile: &str) {
+    if let Some(cache_path) = cache_path(profile) {
+        let _ = fs::remove_file(cache_path);
+    }
+}
+
+/// The decrypted access token is remembered in plaintext in the per-user runtime directory, which lives in memory and is cleared on logout. Without one nothing is remembered, and keyring_cache = 0 turns remembering off.
+fn cache_path(profile: &str) -> Option<PathBuf> {
+    let runtime_dir = env::var_os("XDG_RUNTIME_DIR")?;
+    let profile = check_profile(profile).ok()?;
+    Some(PathBuf::from(runtime_dir).join("pbr").join(profile))
+}
+
+fn cached(profile: &str, cache: Duration) -> Option<String> {
+    let cache_path = cache_path(profile)?;
+    let modified = fs::metadata(&cache_path).ok()?.modified().ok()?;
+    let age = SystemTime::now().duration_since(modified).ok()?;
+    if age > cache {
+        forget(profile);
+        return None;
+    }
+    fs::read_to_string(&cache_path).ok()
+}
+
+fn prompt(message: &str) -> Result<String> {
+    rpassword::prompt_password(message).map_err(|e| keyring_error(&e.to_string()))
+}
+
+fn keyring_error(message: &str) -> PbError {
+    PbError::Config(format!("keyring: {message}"))
+}
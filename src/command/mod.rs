@@ -1,10 +1,33 @@
-mod command;
+mod bulk;
+mod cache;
+mod channel;
 mod chat;
+#[allow(clippy::module_inception)]
+mod command;
+mod config;
+mod daemon;
 mod device;
+mod download;
+mod ephemeral;
+mod exec;
+mod files;
+mod guard;
+mod journal;
+mod keyring;
+mod output;
+mod pipe;
 mod push;
-mod channel;
+mod resolve;
+mod search;
+mod send;
+mod stream;
 mod subscription;
 mod text;
+mod time;
 mod user;
 
-pub use command::*;
\ No newline at end of file
+pub use cache::{Cache, CacheCommands};
+pub use command::*;
+pub use config::{check_profile, Config};
+pub use output::{Output, OutputFormat};
+pub use resolve::Resolver;
@@ -0,0 +1,222 @@
+use clap::ValueEnum;
+use pushbullet_rust::{PbError, Result};
+use serde_json::{Map, Value};
+
+/// Fields of the responses that hold the objects of list commands.
+const LIST_KEYS: [&str; 5] = ["pushes", "devices", "chats", "subscriptions", "channels"];
+
+/// Widest a table cell gets before being cut.
+const MAX_CELL_WIDTH: usize = 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// The response body on one line.
+    Json,
+    /// The response body indented.
+    Pretty,
+    /// Aligned columns, one row per object.
+    Table,
+    /// One object per line, for piping into jq.
+    Jsonl,
+    /// One line per object from --template.
+    Template,
+}
+
+/// How results are printed, from the command line or the profile.
+pub struct Output {
+    pub format: OutputFormat,
+
+    /// Text where every {{field}} is replaced by the field of the object, nested fields written as {{with.email}}.
+    pub template: Option<String>,
+
+    /// Fields kept for every object, in this order.
+    pub columns: Vec<String>,
+}
+
+impl OutputFormat {
+    pub fn parse(name: &str) -> Result<OutputFormat> {
+        OutputFormat::from_str(name, true).map_err(|_| {
+            PbError::Config(format!(
+                "unknown output format \"{name}\", expected json, pretty, table, jsonl or template"
+            ))
+        })
+    }
+}
+
+impl Output {
+    pub fn print(&self, value: &Value) -> Result<()> {
+        for line in self.render(value)? {
+            println!("{line}");
+        }
+        Ok(())
+    }
+
+    /// Lines to print for a response.
+    pub fn render(&self, value: &Value) -> Result<Vec<String>> {
+        let list = list(value);
+        if let Some((_, _, Some(cursor))) = list {
+            if self.format != OutputFormat::Json && self.format != OutputFormat::Pretty {
+                eprintln!("More objects are available with --cursor {cursor}");
+            }
+        }
+
+        let objects: Vec<Value> = match list {
+            Some((_, items, _)) => items.iter().map(|item| self.select(item)).collect(),
+            None => vec![self.select(value)],
+        };
+        let body = match list {
+            _ if self.columns.is_empty() => value.clone(),
+            Some((key, _, _)) => {
+                let mut body = value.clone();
+                body[key] = Value::Array(objects.clone());
+                body
+            }
+            None => objects[0].clone(),
+        };
+
+        Ok(match self.format {
+            OutputFormat::Json => vec![body.to_string()],
+            OutputFormat::Pretty => vec![serde_json::to_string_pretty(&body)?],
+            OutputFormat::Jsonl => objects.iter().map(Value::to_string).collect(),
+            OutputFormat::Table => {
+                let columns = match self.columns.is_empty() {
+                    true => default_columns(list.map(|(key, _, _)| key), &objects),
+                    false => self.columns.clone(),
+                };
+                table(&columns, &objects)
+            }
+            OutputFormat::Template => {
+                let template = self.template.as_deref().ok_or_else(|| {
+                    PbError::Config(String::from("--output template requires --template"))
+                })?;
+                objects
+                    .iter()
+                    .map(|object| fill(template, object))
+                    .collect()
+            }
+        })
+    }
+
+    /// Keep only the selected columns of an object, leaving out the absent ones.
+    fn select(&self, object: &Value) -> Value {
+        if self.columns.is_empty() {
+            return object.clone();
+        }
+        let selected: Map<String, Value> = self
+            .columns
+            .iter()
+            .filter_map(|column| Some((column.clone(), field(object, column)?.clone())))
+            .collect();
+        Value::Object(selected)
+    }
+}
+
+/// Field holding the objects, objects and next page cursor of a list response.
+fn list(value: &Value) -> Option<(&'static str, &Vec<Value>, Option<&str>)> {
+    let (key, items) = LIST_KEYS
+        .iter()
+        .find_map(|key| Some((*key, value.get(*key)?.as_array()?)))?;
+    Some((key, items, value.get("cursor").and_then(Value::as_str)))
+}
+
+/// Field at a dotted path, such as with.email, or named after the path once selected with --columns.
+fn field<'a>(object: &'a Value, path: &str) -> Option<&'a Value> {
+    object.get(path).or_else(|| {
+        path.split('.')
+            .try_fold(object, |value, key| value.get(key))
+    })
+}
+
+/// A field as plain text, strings without their quotes.
+fn text(value: Option<&Value>) -> String {
+    match value {
+        None | Some(Value::Null) => String::new(),
+        Some(Value::String(text)) => text.clone(),
+        Some(value) => value.to_string(),
+    }
+}
+
+fn cell(value: Option<&Value>) -> String {
+    let text = text(value).replace(['\n', '\r', '\t'], " ");
+    match text.chars().count() > MAX_CELL_WIDTH {
+        true => text.chars().take(MAX_CELL_WIDTH - 1).chain(['…']).collect(),
+        false => text,
+    }
+}
+
+/// Columns shown when --columns is not given, the most useful fields of known objects.
+fn default_columns(list_key: Option<&str>, objects: &[Value]) -> Vec<String> {
+    let columns: &[&str] = match list_key {
+        Some("pushes") => &["iden", "type", "title", "body", "url", "file_name"],
+        Some("devices") => &["iden", "nickname", "manufacturer", "model", "active"],
+        Some("chats") => &["iden", "with.name", "with.email", "muted"],
+        Some("subscriptions") => &["iden", "channel.tag", "channel.name", "muted"],
+        _ => &[],
+    };
+    if !columns.is_empty() {
+        return columns.iter().map(|column| column.to_string()).collect();
+    }
+
+    // Every field holding a plain value, in the order they first appear.
+    let mut columns: Vec<String> = vec![];
+    for object in objects.iter().filter_map(Value::as_object) {
+        for (key, value) in object {
+            if !value.is_object() && !value.is_array() && !columns.contains(key) {
+                columns.push(key.clone());
+            }
+        }
+    }
+    columns
+}
+
+fn table(columns: &[String], objects: &[Value]) -> Vec<String> {
+    let rows: Vec<Vec<String>> = objects
+        .iter()
+        .map(|object| {
+            columns
+                .iter()
+                .map(|column| cell(field(object, column)))
+                .collect()
+        })
+        .collect();
+    let header: Vec<String> = columns.iter().map(|column| column.to_uppercase()).collect();
+    let widths: Vec<usize> = (0..columns.len())
+        .map(|i| {
+            rows.iter()
+                .chain([&header])
+                .map(|row| row[i].chars().count())
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    [&header]
+        .into_iter()
+        .chain(&rows)
+        .map(|row| {
+            let line: Vec<String> = row
+                .iter()
+                .zip(&widths)
+                .map(|(text, width)| format!("{text:width$}"))
+                .collect();
+            line.join("  ").trim_end().to_owned()
+        })
+        .collect()
+}
+
+/// Replace every {{field}} of template by the field of object.
+fn fill(template: &str, object: &Value) -> String {
+    let mut filled = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start..].find("}}") else {
+            break;
+        };
+        filled.push_str(&rest[..start]);
+        let path = rest[start + 2..start + end].trim();
+        filled.push_str(&text(field(object, path)));
+        rest = &rest[start + end + 2..];
+    }
+    filled.push_str(rest);
+    filled
+}
@@ -0,0 +1,112 @@
+use clap::Args;
+use pushbullet_rust::{push::CreateRequest, Client, Result};
+use serde_json::{json, Value};
+
+use super::{read_stdin, Request, TargetArgs};
+
+#[derive(Args)]
+pub struct PipeArgs {
+    /// Title of the pushes, numbered when the input needs several.
+    #[arg(long)]
+    pub title: Option<String>,
+
+    /// Only send the last lines of the input.
+    #[arg(long, value_name = "LINES")]
+    pub tail: Option<usize>,
+
+    /// Largest body of a single push in bytes, longer input is split into several pushes.
+    #[arg(long, default_value = "4000")]
+    pub chunk_size: usize,
+
+    #[command(flatten)]
+    pub target: TargetArgs,
+}
+
+impl Request for PipeArgs {
+    /// Send stdin as note pushes once it is closed, nothing being sent for empty input.
+    fn request(&self, client: &Client) -> Result<Value> {
+        let input = read_stdin()?;
+        let input = match self.tail {
+            Some(lines) => tail(&input, lines),
+            None => &input,
+        };
+        let chunks = chunks(input.trim_end(), self.chunk_size.max(1));
+
+        let mut pushes = vec![];
+        for (i, chunk) in chunks.iter().enumerate() {
+            let title = match (&self.title, chunks.len()) {
+                (Some(title), 1) => Some(title.clone()),
+                (Some(title), count) => Some(format!("{title} ({}/{count})", i + 1)),
+                (None, 1) => None,
+                (None, count) => Some(format!("{}/{count}", i + 1)),
+            };
+            let request = CreateRequest {
+                t: Some(String::from("note")),
+                title,
+                body: Some(chunk.clone()),
+                ..self.target.to_request()
+            };
+            match client.pushes().create(&request) {
+                Ok(push) => pushes.push(push),
+                Err(e) => {
+                    // Sending the input again would repeat the chunks already sent.
+                    if !pushes.is_empty() {
+                        let idens: Vec<&str> =
+                            pushes.iter().map(|push| push.iden.as_str()).collect();
+                        eprintln!(
+                            "Sent {} of {} chunks before failing: {}",
+                            pushes.len(),
+                            chunks.len(),
+                            idens.join(", ")
+                        );
+                    }
+                    return Err(e);
+                }
+            }
+        }
+        Ok(json!({ "pushes": pushes }))
+    }
+}
+
+/// The last lines of text.
+pub fn tail(text: &str, lines: usize) -> &str {
+    if lines == 0 {
+        return "";
+    }
+    let text = text.trim_end_matches('\n');
+    let start = text
+        .rmatch_indices('\n')
+        .nth(lines - 1)
+        .map_or(0, |(i, _)| i + 1);
+    &text[start..]
+}
+
+/// Split text into non-empty chunks of at most size bytes, between lines when possible and never inside a character.
+pub fn chunks(text: &str, size: usize) -> Vec<String> {
+    let mut chunks = vec![];
+    let mut chunk = String::new();
+    for line in text.split_inclusive('\n') {
+        if chunk.len() + line.len() > size && !chunk.is_empty() {
+            chunks.push(chunk.trim_end_matches('\n').to_owned());
+            chunk.clear();
+        }
+        let mut line = line;
+        while line.len() > size {
+            let mut end = size;
+            while !line.is_char_boundary(end) {
+                end -= 1;
+            }
+            // A character wider than size still goes whole into its own chunk.
+            if end == 0 {
+                end = line.chars().next().unwrap().len_utf8();
+            }
+            chunks.push(line[..end].to_owned());
+            line = &line[end..];
+        }
+        chunk.push_str(line);
+    }
+    chunks.push(chunk.trim_end_matches('\n').to_owned());
+    // Blank lines left over at a chunk boundary would be sent as empty notes.
+    chunks.retain(|chunk| !chunk.is_empty());
+    chunks
+}
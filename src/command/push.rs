@@ -1,10 +1,22 @@
-use std::error::Error;
-
 use clap::{Args, Subcommand};
-use reqwest::blocking::RequestBuilder;
-use serde::{Deserialize, Serialize};
-
-use super::{upload, upload_request, Request};
+use pushbullet_rust::{
+    push::{CreateRequest, ListRequest, UpdateRequest},
+    Client, PbError, Result,
+};
+use serde_json::{json, Value};
+
+use super::{
+    bulk::{DeleteArgs, DismissArgs, ExportArgs},
+    cache::Cache,
+    collect_pages,
+    download::DownloadArgs,
+    files::{send_all, FileTypes, Files, TypeOverride},
+    guard::GuardArgs,
+    journal::Kind,
+    progress_bars, read_stdin,
+    search::SearchArgs,
+    truncate, upload, Request,
+};
 
 #[derive(Args)]
 pub struct PaginationArgs {
@@ -23,43 +35,68 @@ pub struct PaginationArgs {
     /// You can specify a limit parameter that return a list of objects to get a smaller number of objects on each page.
     #[arg(long, default_value = "500")]
     limit: Option<i32>,
+
+    /// Follow the cursor until the last page and print every push as a single list.
+    #[arg(long)]
+    all: bool,
+
+    /// Stop after this many pushes when using --all, 0 for no limit.
+    #[arg(long, default_value = "10000", requires = "all")]
+    max_items: usize,
 }
 
 impl PaginationArgs {
-    pub fn to_query(&self) -> Vec<(String, String)> {
-        let mut query: Vec<(String, String)> = vec![];
-        if let Some(modified_after) = &self.modified_after {
-            query.push((String::from("modified_after"), modified_after.to_owned()));
-        }
-        if let Some(active) = self.active {
-            query.push((String::from("active"), active.to_string()));
-        }
-        if let Some(cursor) = &self.cursor {
-            query.push((String::from("cursor"), cursor.to_owned()));
-        }
-        if let Some(limit) = self.limit {
-            query.push((String::from("limit"), limit.to_string()));
+    /// The cached pushes passing --modified-after and --active.
+    pub fn offline(&self, cache: &Cache) -> Result<Value> {
+        let modified_after = match &self.modified_after {
+            Some(modified_after) => Some(
+                modified_after
+                    .parse::<f64>()
+                    .map_err(|e| PbError::Config(format!("--modified-after: {e}")))?,
+            ),
+            None => None,
+        };
+        let mut pushes = cache.items("pushes")?;
+        pushes.retain(|push| {
+            modified_after.is_none_or(|after| push["modified"].as_f64().unwrap_or(0.0) > after)
+                && self
+                    .active
+                    .is_none_or(|active| push["active"] == json!(active))
+        });
+        truncate(&mut pushes, self.all, self.limit, self.max_items);
+        Ok(json!({ "pushes": pushes }))
+    }
+
+    pub fn to_request(&self) -> ListRequest {
+        ListRequest {
+            modified_after: self.modified_after.clone(),
+            active: self.active,
+            cursor: self.cursor.clone(),
+            limit: self.limit,
         }
-        query
     }
 }
 
 #[derive(Subcommand)]
+#[allow(clippy::large_enum_variant)]
 pub enum PushCommands {
     /// Request push history.
     List(PaginationArgs),
 
+    /// Search push history by type, sender, device, dismissed state, creation date, text or file.
+    Search(SearchArgs),
+
     /// Send a push to a device or another person.
     Create {
         /// Type of the push, one of "note", "file", "link".
-        #[arg(long, name = "type")]
+        #[arg(long = "type", value_name = "TYPE")]
         t: Option<String>,
 
         /// Title of the push, used for all types of pushes
         #[arg(long)]
         title: Option<String>,
 
-        /// Body of the push, used for all types of pushes
+        /// Body of the push, used for all types of pushes. "-" reads it from stdin.
         #[arg(long)]
         body: Option<String>,
 
@@ -67,14 +104,30 @@ pub enum PushCommands {
         #[arg(long)]
         url: Option<String>,
 
-        /// File name, used for type="file" pushes
+        /// File name, used for type="file" pushes. Repeat it or use a glob such as 'reports/*.pdf' to send several files, one push each.
+        #[arg(long)]
+        file_name: Vec<String>,
+
+        /// Send every file under this directory, one push each.
         #[arg(long)]
-        file_name: Option<String>,
+        dir: Option<String>,
 
-        /// File mime type, used for type="file" pushes
+        /// Archive the directory into a single zip file and send it.
+        #[arg(long, requires = "dir")]
+        zip: bool,
+
+        /// Number of files uploaded at the same time.
+        #[arg(long, default_value = "4")]
+        jobs: usize,
+
+        /// File mime type, used for type="file" pushes. Detected from the content and extension of uploaded files when not given.
         #[arg(long)]
         file_type: Option<String>,
 
+        /// MIME type of the uploaded files matching a glob, such as '*.log=text/plain'. Repeatable, the first matching one wins over --file-type.
+        #[arg(long, value_name = "PATTERN=TYPE")]
+        type_override: Vec<TypeOverride>,
+
         /// File download url, used for type="file" pushes
         #[arg(long)]
         file_url: Option<String>,
@@ -105,6 +158,10 @@ pub enum PushCommands {
 
         #[arg(long)]
         data_binary: Option<String>,
+
+        /// Don't show the upload progress bar.
+        #[arg(long)]
+        quiet: bool,
     },
 
     /// Update a push.
@@ -120,81 +177,81 @@ pub enum PushCommands {
         data_binary: Option<String>,
     },
 
-    /// Delete a push.
-    Delete {
-        /// Unique identifier for this object
-        iden: String,
-    },
-
-    /// Delete all pushes belonging to the current user. This call is asynchronous, the pushes will be deleted after the call returns.
-    DeleteAll,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct CreateRequest {
-    /// Type of the push, one of "note", "file", "link".
-    #[serde(rename = "type")]
-    t: Option<String>,
-
-    /// Title of the push, used for all types of pushes
-    title: Option<String>,
+    /// Mark pushes as dismissed, given by iden or by filters such as --all-undismissed or --older-than 7d.
+    Dismiss(DismissArgs),
 
-    /// Body of the push, used for all types of pushes
-    body: Option<String>,
+    /// Delete pushes, given by iden or by filters such as --older-than 30d --type link.
+    Delete(DeleteArgs),
 
-    /// URL field, used for type="link" pushes
-    url: Option<String>,
+    /// Print the pushes matching filters, or write them to a file as JSON lines.
+    Export(ExportArgs),
 
-    /// File name, used for type="file" pushes
-    file_name: Option<String>,
+    /// Download the files of file pushes, keeping their file names.
+    Download(DownloadArgs),
 
-    /// File mime type, used for type="file" pushes
-    file_type: Option<String>,
-
-    /// File download url, used for type="file" pushes
-    file_url: Option<String>,
-
-    /// Device iden of the sending device. Optional.
-    source_device_iden: Option<String>,
-
-    /// Device iden of the target device, if sending to a single device. Appears as target_device_iden on the push.
-    device_iden: Option<String>,
-
-    /// Client iden of the target client, sends a push to all users who have granted access to this client. The current user must own this client.
-    client_iden: Option<String>,
-
-    /// Channel tag of the target channel, sends a push to all people who are subscribed to this channel. The current user must own this channel.
-    channel_tag: Option<String>,
-
-    /// Email address to send the push to. If there is a pushbullet user with this address, they get a push, otherwise they get an email.
-    email: Option<String>,
+    /// Delete all pushes belonging to the current user. This call is asynchronous, the pushes will be deleted after the call returns.
+    DeleteAll {
+        /// Most pushes shown before confirming and kept in the undo journal, 0 for no limit. All pushes are deleted regardless.
+        #[arg(long, default_value = "10000")]
+        max_items: usize,
 
-    /// Unique identifier set by the client, used to identify a push in case you receive it from /v2/everything before the call to /v2/pushes has completed. This should be a unique value. Pushes with guid set are mostly idempotent, meaning that sending another push with the same guid is unlikely to create another push (it will return the previously created push).
-    guid: Option<String>,
+        #[command(flatten)]
+        guard: GuardArgs,
+    },
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct UpdateRequest {
-    /// Marks a push as having been dismissed by the user, will cause any notifications for the push to be hidden if possible.
-    dismissed: Option<bool>,
+impl PushCommands {
+    /// Send created pushes to device when no other target is given.
+    pub fn set_default_device(&mut self, device: &str) {
+        if let PushCommands::Create {
+            device_iden: device_iden @ None,
+            client_iden: None,
+            channel_tag: None,
+            email: None,
+            data_binary: None,
+            ..
+        } = self
+        {
+            *device_iden = Some(device.to_owned());
+        }
+    }
+
+    /// Send pushes from device when no source device is given.
+    pub fn set_default_source(&mut self, device: &str) {
+        if let PushCommands::Create {
+            source_device_iden: source_device_iden @ None,
+            data_binary: None,
+            ..
+        } = self
+        {
+            *source_device_iden = Some(device.to_owned());
+        }
+    }
 }
 
 impl Request for PushCommands {
-    fn build_request(&self, access_token: &str) -> Result<RequestBuilder, Box<dyn Error>> {
+    fn request(&self, client: &Client) -> Result<Value> {
         match self {
-            PushCommands::List(args) => {
-                let request_builder = reqwest::blocking::Client::new()
-                    .get("https://api.pushbullet.com/v2/pushes")
-                    .query(&args.to_query());
-                Ok(request_builder)
-            }
+            PushCommands::List(args) if args.all => collect_pages(
+                "pushes",
+                client.pushes().iter(args.to_request()),
+                args.max_items,
+            ),
+            PushCommands::List(args) => Ok(serde_json::to_value(
+                client.pushes().list(&args.to_request())?,
+            )?),
+            PushCommands::Search(args) => args.request(client),
             PushCommands::Create {
                 t,
                 title,
                 body,
                 url,
                 file_name,
+                dir,
+                zip,
+                jobs,
                 file_type,
+                type_override,
                 file_url,
                 source_device_iden,
                 device_iden,
@@ -203,73 +260,58 @@ impl Request for PushCommands {
                 email,
                 guid,
                 data_binary,
+                quiet,
             } => {
                 let request = match data_binary {
-                    Some(data_binary) => match serde_json::from_str(&data_binary) {
-                        Ok(request) => request,
-                        Err(error) => {
-                            return Err(Box::new(error));
-                        }
-                    },
+                    Some(data_binary) => serde_json::from_str(data_binary)?,
                     None => {
-                        let mut file_name = file_name.clone();
-                        let mut file_type = file_type.clone();
-                        let mut file_url = file_url.clone();
-
-                        let file_name_clone = file_name.clone();
-                        if let Some(t) = t {
-                            if t == "file" && file_name_clone.is_some() {
-                                match upload_request(
-                                    access_token,
-                                    file_name.unwrap(),
-                                    file_type.to_owned(),
-                                ) {
-                                    Ok(response) => {
-                                        let upload_url = response.upload_url;
-                                        match upload(
-                                            access_token,
-                                            file_name_clone.unwrap().as_str(),
-                                            &upload_url,
-                                        ) {
-                                            Ok(_) => {
-                                                file_name = Some(response.file_name);
-                                                file_type = Some(response.file_type);
-                                                file_url = Some(response.file_url);
-                                            }
-                                            Err(error) => {
-                                                return Err(error);
-                                            }
-                                        };
-                                    }
-                                    Err(error) => {
-                                        return Err(error);
-                                    }
-                                };
-                            }
-                        }
-
-                        CreateRequest {
+                        let mut request = CreateRequest {
                             t: t.clone(),
                             title: title.clone(),
-                            body: body.clone(),
+                            body: match body.as_deref() {
+                                Some("-") => Some(read_stdin()?),
+                                _ => body.clone(),
+                            },
                             url: url.clone(),
-                            file_name: file_name,
-                            file_type: file_type,
-                            file_url: file_url,
+                            file_name: file_name.first().cloned(),
+                            file_type: file_type.clone(),
+                            file_url: file_url.clone(),
                             source_device_iden: source_device_iden.clone(),
                             device_iden: device_iden.clone(),
                             client_iden: client_iden.clone(),
                             channel_tag: channel_tag.clone(),
                             email: email.clone(),
                             guid: guid.clone(),
+                        };
+                        // A file_url points at a file already uploaded.
+                        if (t.as_deref() == Some("file") && file_url.is_none()) || dir.is_some() {
+                            let files = Files::collect(file_name, dir.as_deref(), *zip)?;
+                            let bars = progress_bars(*quiet);
+                            let types = FileTypes {
+                                file_type: file_type.clone(),
+                                overrides: type_override.clone(),
+                            };
+                            if files.paths.len() > 1 || dir.is_some() {
+                                return send_all(
+                                    client,
+                                    &request,
+                                    &files.paths,
+                                    &types,
+                                    *jobs,
+                                    &bars,
+                                );
+                            }
+                            let response =
+                                upload(client, &files.paths[0], types.get(&files.paths[0]), &bars)?;
+                            request.t = Some(String::from("file"));
+                            request.file_name = Some(response.file_name);
+                            request.file_type = Some(response.file_type);
+                            request.file_url = Some(response.file_url);
                         }
+                        request
                     }
                 };
-
-                let request_builder = reqwest::blocking::Client::new()
-                    .post("https://api.pushbullet.com/v2/pushes")
-                    .json(&request);
-                Ok(request_builder)
+                Ok(serde_json::to_value(client.pushes().create(&request)?)?)
             }
             PushCommands::Update {
                 iden,
@@ -277,30 +319,38 @@ impl Request for PushCommands {
                 data_binary,
             } => {
                 let request = match data_binary {
-                    Some(data_binary) => match serde_json::from_str(data_binary) {
-                        Ok(request) => request,
-                        Err(error) => {
-                            return Err(Box::new(error));
-                        }
-                    },
+                    Some(data_binary) => serde_json::from_str(data_binary)?,
                     None => UpdateRequest {
-                        dismissed: dismissed.clone(),
+                        dismissed: *dismissed,
                     },
                 };
-                let request_builder = reqwest::blocking::Client::new()
-                    .post(format!("https://api.pushbullet.com/v2/pushes/{}", iden))
-                    .json(&request);
-                Ok(request_builder)
+                Ok(serde_json::to_value(
+                    client.pushes().update(iden, &request)?,
+                )?)
             }
-            PushCommands::Delete { iden } => {
-                let request_builder = reqwest::blocking::Client::new()
-                    .delete(format!("https://api.pushbullet.com/v2/pushes/{}", iden));
-                Ok(request_builder)
-            }
-            PushCommands::DeleteAll => {
-                let request_builder =
-                    reqwest::blocking::Client::new().delete("https://api.pushbullet.com/v2/pushes");
-                Ok(request_builder)
+            PushCommands::Dismiss(args) => args.request(client),
+            PushCommands::Delete(args) => args.request(client),
+            PushCommands::Export(args) => args.request(client),
+            PushCommands::Download(args) => args.request(client),
+            PushCommands::DeleteAll { max_items, guard } => {
+                // Fetched to be shown before confirming and kept in the undo journal.
+                let request = ListRequest {
+                    active: Some(true),
+                    limit: Some(500),
+                    ..Default::default()
+                };
+                let mut paginator = client.pushes().iter(request);
+                let mut pushes = vec![];
+                while *max_items == 0 || pushes.len() < *max_items {
+                    match paginator.next() {
+                        Some(push) => pushes.push(serde_json::to_value(push?)?),
+                        None => break,
+                    }
+                }
+                if paginator.has_more() {
+                    eprintln!("Only the first {max_items} pushes are shown and kept in the undo journal (see --max-items)");
+                }
+                guard.delete_all(client, Kind::Push, pushes, || client.pushes().delete_all())
             }
         }
     }
@@ -0,0 +1,222 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+};
+
+use pushbullet_rust::{Client, Pagination, PbError, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{cache::Cache, config::write_private, journal::Kind, time::now};
+
+/// Seconds the listed devices, chats and subscriptions are used for before being listed again.
+const NAMES_TTL: f64 = 300.0;
+
+/// Objects of a kind as last listed.
+#[derive(Serialize, Deserialize)]
+struct Listing {
+    time: f64,
+    items: Vec<Value>,
+}
+
+/// Turns the names given on the command line into idens: device nicknames, chat emails and channel tags, or unique iden prefixes.
+pub struct Resolver<'a> {
+    /// None with --offline, names being resolved from the synced cache only.
+    client: Option<&'a Client>,
+    cache: &'a Cache,
+    listings: Option<BTreeMap<String, Listing>>,
+    /// Kinds listed by this resolver, not worth listing again.
+    listed: BTreeSet<&'static str>,
+}
+
+impl<'a> Resolver<'a> {
+    pub fn new(client: Option<&'a Client>, cache: &'a Cache) -> Resolver<'a> {
+        Resolver {
+            client,
+            cache,
+            listings: None,
+            listed: BTreeSet::new(),
+        }
+    }
+
+    /// Replace reference with the iden of the object of kind it names, leaving it as is when nothing matches.
+    pub fn resolve(&mut self, kind: Kind, reference: &mut String) -> Result<()> {
+        if let Some(object) = self.find(kind, reference)? {
+            *reference = iden(&object).to_owned();
+        }
+        Ok(())
+    }
+
+    /// Resolve the target of a push, a device, the email of a chat or the tag of a channel.
+    ///
+    /// Email addresses and idens of a full length, or listed before, are used as they are without listing anything.
+    pub fn resolve_target(
+        &mut self,
+        device_iden: &mut Option<String>,
+        email: &mut Option<String>,
+        channel_tag: &mut Option<String>,
+    ) -> Result<()> {
+        let Some(reference) = device_iden.as_mut() else {
+            return Ok(());
+        };
+        if reference.contains('@') {
+            *email = device_iden.take();
+            return Ok(());
+        }
+        if looks_like_iden(reference) || self.is_listed_iden(Kind::Device, reference) {
+            return Ok(());
+        }
+        match self.find_any(&[Kind::Device, Kind::Chat, Kind::Subscription], reference)? {
+            Some((Kind::Device, device)) => *reference = iden(&device).to_owned(),
+            Some((Kind::Chat, chat)) => {
+                *email = chat["with"]["email"].as_str().map(str::to_owned);
+                *device_iden = None;
+            }
+            Some((_, subscription)) => {
+                *channel_tag = subscription["channel"]["tag"].as_str().map(str::to_owned);
+                *device_iden = None;
+            }
+            None => (),
+        }
+        Ok(())
+    }
+
+    /// Whether reference is the exact iden of an object of kind in the names cache, however old.
+    fn is_listed_iden(&mut self, kind: Kind, reference: &str) -> bool {
+        self.client.is_some()
+            && self
+                .listings()
+                .get(kind.path())
+                .is_some_and(|listing| listing.items.iter().any(|object| iden(object) == reference))
+    }
+
+    /// The object of kind named by reference, listing the objects again when the cached ones do not match.
+    fn find(&mut self, kind: Kind, reference: &str) -> Result<Option<Value>> {
+        Ok(self.find_any(&[kind], reference)?.map(|(_, object)| object))
+    }
+
+    /// The object named by reference and its kind, the first of kinds to match, looking through the cached objects of every kind before listing any again.
+    fn find_any(&mut self, kinds: &[Kind], reference: &str) -> Result<Option<(Kind, Value)>> {
+        for refresh in [false, true] {
+            if refresh && self.client.is_none() {
+                break;
+            }
+            for &kind in kinds {
+                if refresh && self.listed.contains(kind.path()) {
+                    continue;
+                }
+                let items = self.items(kind, refresh)?;
+                let candidates = candidates(kind, &items, reference);
+                match candidates[..] {
+                    [] => continue,
+                    [object] => return Ok(Some((kind, object.clone()))),
+                    _ => {
+                        let names: Vec<String> = candidates
+                            .iter()
+                            .map(|object| format!("{} ({})", name(kind, object), iden(object)))
+                            .collect();
+                        return Err(PbError::Config(format!(
+                            "{reference:?} matches several {}: {}",
+                            kind.path(),
+                            names.join(", ")
+                        )));
+                    }
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// The names cache, read from disk the first time.
+    fn listings(&mut self) -> &mut BTreeMap<String, Listing> {
+        let path = self.cache.path("names");
+        self.listings.get_or_insert_with(|| {
+            fs::read(&path)
+                .ok()
+                .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+                .unwrap_or_default()
+        })
+    }
+
+    /// Active objects of kind, from the names cache unless refresh or stale, or from the synced cache with --offline.
+    fn items(&mut self, kind: Kind, refresh: bool) -> Result<Vec<Value>> {
+        let Some(client) = self.client else {
+            return Ok(self.cache.items(kind.path()).unwrap_or_default());
+        };
+        if let Some(listing) = self.listings().get(kind.path()) {
+            if !refresh && now() - listing.time < NAMES_TTL {
+                return Ok(listing.items.clone());
+            }
+        }
+
+        let pagination = Pagination::default();
+        let items: Vec<Value> = match kind {
+            Kind::Device => to_values(client.devices().iter(pagination))?,
+            Kind::Chat => to_values(client.chats().iter(pagination))?,
+            Kind::Subscription => to_values(client.subscriptions().iter(pagination))?,
+            Kind::Push | Kind::Text => vec![],
+        };
+        let items: Vec<Value> = items
+            .into_iter()
+            .filter(|item| item["active"] != false)
+            .collect();
+        self.listed.insert(kind.path());
+        let path = self.cache.path("names");
+        let listings = self.listings();
+        listings.insert(
+            kind.path().to_owned(),
+            Listing {
+                time: now(),
+                items: items.clone(),
+            },
+        );
+        write_private(&path, &serde_json::to_vec(listings)?)
+            .map_err(|e| PbError::Io(format!("{}: {e}", path.display())))?;
+        Ok(items)
+    }
+}
+
+fn to_values<I, T>(items: I) -> Result<Vec<Value>>
+where
+    I: Iterator<Item = Result<T>>,
+    T: Serialize,
+{
+    items.map(|item| Ok(serde_json::to_value(item?)?)).collect()
+}
+
+/// Objects matching reference: its exact iden, else its name ignoring case, else a prefix of its iden.
+fn candidates<'v>(kind: Kind, items: &'v [Value], reference: &str) -> Vec<&'v Value> {
+    if let Some(object) = items.iter().find(|object| iden(object) == reference) {
+        return vec![object];
+    }
+    let named: Vec<&Value> = items
+        .iter()
+        .filter(|object| name(kind, object).eq_ignore_ascii_case(reference))
+        .collect();
+    if !named.is_empty() {
+        return named;
+    }
+    items
+        .iter()
+        .filter(|object| iden(object).starts_with(reference))
+        .collect()
+}
+
+/// Whether reference has the length and alphabet of an iden handed out by the API.
+fn looks_like_iden(reference: &str) -> bool {
+    reference.len() >= 20 && reference.chars().all(|c| c.is_ascii_alphanumeric())
+}
+
+fn iden(object: &Value) -> &str {
+    object["iden"].as_str().unwrap_or_default()
+}
+
+fn name(kind: Kind, object: &Value) -> &str {
+    let name = match kind {
+        Kind::Device => &object["nickname"],
+        Kind::Chat => &object["with"]["email"],
+        Kind::Subscription => &object["channel"]["tag"],
+        Kind::Push | Kind::Text => &Value::Null,
+    };
+    name.as_str().unwrap_or_default()
+}
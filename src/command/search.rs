@@ -0,0 +1,146 @@
+use clap::Args;
+use pushbullet_rust::{
+    push::{ListRequest, Push},
+    Client, Result,
+};
+use regex::Regex;
+use serde_json::{json, Value};
+
+use super::{cache::Cache, time::parse_time};
+
+/// Filters of pb push search, applied to the push history since the server cannot search it.
+#[derive(Args, Clone)]
+pub struct SearchArgs {
+    /// Only pushes of this type, such as note, link or file.
+    #[arg(long = "type", value_name = "TYPE")]
+    pub t: Option<String>,
+
+    /// Only pushes sent by this email address.
+    #[arg(long, value_name = "EMAIL")]
+    pub sender: Option<String>,
+
+    /// Only pushes sent to this device iden.
+    #[arg(long, value_name = "IDEN")]
+    pub device: Option<String>,
+
+    /// Only dismissed pushes with true, only pushes not dismissed with false.
+    #[arg(long)]
+    pub dismissed: Option<bool>,
+
+    /// Only pushes created since this time: a unix timestamp, a duration ago such as 2d, or a UTC date such as 2024-06-01.
+    #[arg(long, value_name = "TIME", value_parser = parse_time)]
+    pub since: Option<f64>,
+
+    /// Only pushes created before this time, in the same forms as --since. --older-than 30d keeps the pushes created more than 30 days ago.
+    #[arg(long, visible_alias = "older-than", value_name = "TIME", value_parser = parse_time)]
+    pub until: Option<f64>,
+
+    /// Only pushes whose title, body, url or file name contains this text, ignoring case.
+    #[arg(long)]
+    pub text: Option<String>,
+
+    /// Only pushes whose title, body, url or file name matches this regular expression. Prefix it with (?i) to ignore case.
+    #[arg(long)]
+    pub regex: Option<Regex>,
+
+    /// Only pushes with a file with true, only pushes without one with false.
+    #[arg(long)]
+    pub has_file: Option<bool>,
+
+    /// Stop after this many matching pushes, 0 for no limit.
+    #[arg(long, default_value = "10000")]
+    pub max_items: usize,
+}
+
+impl SearchArgs {
+    pub fn request(&self, client: &Client) -> Result<Value> {
+        Ok(json!({ "pushes": self.find(client)? }))
+    }
+
+    /// Pushes of the push history passing every filter, up to --max-items.
+    pub fn find(&self, client: &Client) -> Result<Vec<Push>> {
+        // A push created since a time was also modified since then, so the server can skip older pages.
+        let request = ListRequest {
+            modified_after: self.since.map(|since| since.to_string()),
+            active: Some(true),
+            cursor: None,
+            limit: Some(500),
+        };
+        let mut pushes = vec![];
+        for push in client.pushes().iter(request) {
+            let push = push?;
+            if self.matches(&push) {
+                pushes.push(push);
+                if pushes.len() == self.max_items {
+                    break;
+                }
+            }
+        }
+        Ok(pushes)
+    }
+
+    /// Search the cached pushes.
+    pub fn offline(&self, cache: &Cache) -> Result<Value> {
+        let mut pushes = vec![];
+        for push in cache.items("pushes")? {
+            let push: Push = serde_json::from_value(push)?;
+            if self.matches(&push) {
+                pushes.push(push);
+                if pushes.len() == self.max_items {
+                    break;
+                }
+            }
+        }
+        Ok(json!({ "pushes": pushes }))
+    }
+
+    /// Whether no filter was given, every push matching.
+    pub fn is_empty(&self) -> bool {
+        self.t.is_none()
+            && self.sender.is_none()
+            && self.device.is_none()
+            && self.dismissed.is_none()
+            && self.since.is_none()
+            && self.until.is_none()
+            && self.text.is_none()
+            && self.regex.is_none()
+            && self.has_file.is_none()
+    }
+
+    /// Whether push passes every filter given.
+    pub fn matches(&self, push: &Push) -> bool {
+        let texts = [&push.title, &push.body, &push.url, &push.file_name];
+        let texts = texts.iter().filter_map(|text| text.as_deref());
+
+        self.t.as_ref().is_none_or(|t| push.t.as_ref() == Some(t))
+            && self.sender.as_ref().is_none_or(|sender| {
+                [&push.sender_email, &push.sender_email_normalized]
+                    .iter()
+                    .any(|email| {
+                        email
+                            .as_ref()
+                            .is_some_and(|e| e.eq_ignore_ascii_case(sender))
+                    })
+            })
+            && self
+                .device
+                .as_ref()
+                .is_none_or(|device| push.target_device_iden.as_ref() == Some(device))
+            && self
+                .dismissed
+                .is_none_or(|dismissed| push.dismissed.unwrap_or(false) == dismissed)
+            && self.since.is_none_or(|since| push.created >= since)
+            && self.until.is_none_or(|until| push.created < until)
+            && self.text.as_ref().is_none_or(|text| {
+                let text = text.to_lowercase();
+                texts.clone().any(|t| t.to_lowercase().contains(&text))
+            })
+            && self
+                .regex
+                .as_ref()
+                .is_none_or(|regex| texts.clone().any(|t| regex.is_match(t)))
+            && self
+                .has_file
+                .is_none_or(|has_file| push.file_url.is_some() == has_file)
+    }
+}
@@ -0,0 +1,127 @@
+use std::path::Path;
+
+use clap::{Args, ValueEnum};
+use pushbullet_rust::{push::CreateRequest, Client, Result};
+use reqwest::Url;
+use serde_json::Value;
+
+use super::{progress_bars, upload, Request, TargetArgs};
+
+/// Longest title taken from the first line of a note.
+const MAX_TITLE_LENGTH: usize = 50;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum PushType {
+    Note,
+    Link,
+    File,
+}
+
+#[derive(Args)]
+pub struct SendArgs {
+    /// A url sends a link, the path of an existing file uploads and sends the file, anything else sends a note.
+    pub thing: String,
+
+    /// Send this type of push instead of detecting it.
+    #[arg(long = "type", value_name = "TYPE", value_enum)]
+    pub t: Option<PushType>,
+
+    /// Title of the push, by default the first line of a note or the host of a link.
+    #[arg(long)]
+    pub title: Option<String>,
+
+    /// Body of the push. A note's text becomes its title when a body is given, or is put before the body when a title is given too.
+    #[arg(long)]
+    pub body: Option<String>,
+
+    /// MIME type of a file, detected from its content and extension when not given.
+    #[arg(long)]
+    pub file_type: Option<String>,
+
+    /// Don't show the upload progress bar.
+    #[arg(long)]
+    pub quiet: bool,
+
+    #[command(flatten)]
+    pub target: TargetArgs,
+}
+
+impl SendArgs {
+    /// Type of push for thing: a url with a host is a link, an existing file a file, anything else a note.
+    pub fn push_type(&self) -> PushType {
+        if let Some(t) = self.t {
+            return t;
+        }
+        if Url::parse(&self.thing).is_ok_and(|url| url.host_str().is_some()) {
+            PushType::Link
+        } else if Path::new(&self.thing).is_file() {
+            PushType::File
+        } else {
+            PushType::Note
+        }
+    }
+}
+
+impl Request for SendArgs {
+    fn request(&self, client: &Client) -> Result<Value> {
+        let mut request = CreateRequest {
+            title: self.title.clone(),
+            body: self.body.clone(),
+            ..self.target.to_request()
+        };
+
+        match self.push_type() {
+            PushType::Note => {
+                request.t = Some(String::from("note"));
+                let thing = self.thing.clone();
+                (request.title, request.body) = match (request.title, request.body) {
+                    (None, None) => {
+                        let (title, body) = split_note(&self.thing);
+                        (Some(title), body)
+                    }
+                    (None, Some(body)) => (Some(thing), Some(body)),
+                    (Some(title), None) => (Some(title), Some(thing)),
+                    (Some(title), Some(body)) => (Some(title), Some(format!("{thing}\n\n{body}"))),
+                };
+            }
+            PushType::Link => {
+                request.t = Some(String::from("link"));
+                request.url = Some(self.thing.clone());
+                if request.title.is_none() {
+                    request.title = Url::parse(&self.thing)
+                        .ok()
+                        .and_then(|url| url.host_str().map(str::to_owned));
+                }
+            }
+            PushType::File => {
+                // The file name is shown on file pushes, so no title is inferred.
+                let response = upload(
+                    client,
+                    &self.thing,
+                    self.file_type.clone(),
+                    &progress_bars(self.quiet),
+                )?;
+                request.t = Some(String::from("file"));
+                request.file_name = Some(response.file_name);
+                request.file_type = Some(response.file_type);
+                request.file_url = Some(response.file_url);
+            }
+        }
+
+        Ok(serde_json::to_value(client.pushes().create(&request)?)?)
+    }
+}
+
+/// Title and body of a note: the first line as title and the remaining lines as body. A first line too long for a title is cut and the whole text kept as body.
+fn split_note(text: &str) -> (String, Option<String>) {
+    let text = text.trim();
+    let (first_line, rest) = text.split_once('\n').unwrap_or((text, ""));
+    let first_line = first_line.trim();
+    if first_line.chars().count() > MAX_TITLE_LENGTH {
+        let title: String = first_line.chars().take(MAX_TITLE_LENGTH - 1).collect();
+        return (format!("{}…", title.trim_end()), Some(text.to_owned()));
+    }
+    let rest = rest.trim();
+    let body = (!rest.is_empty()).then(|| rest.to_owned());
+    (first_line.to_owned(), body)
+}
@@ -0,0 +1,75 @@
+use std::{thread, time::Duration};
+
+use clap::Args;
+use pushbullet_rust::{stream::STREAM_URL, Client, PbError, Result};
+
+use super::ephemeral::encryption_key;
+
+#[derive(Args)]
+pub struct StreamArgs {
+    /// Realtime event stream endpoint, the access token is appended to it as the last path segment.
+    #[arg(long, env = "PB_STREAM_URL", default_value = STREAM_URL)]
+    pub url: String,
+
+    /// Seconds without any message before the connection is considered dead. The server sends a nop every 30 seconds.
+    #[arg(long, default_value = "90")]
+    pub nop_timeout: u64,
+
+    /// Seconds to wait before reconnecting after the connection is lost.
+    #[arg(long, default_value = "5")]
+    pub reconnect_delay: u64,
+
+    /// Exit instead of reconnecting when the connection is lost.
+    #[arg(long)]
+    pub no_reconnect: bool,
+
+    /// Decrypt end-to-end encrypted ephemerals with the password set in the Pushbullet apps, read from PB_ENCRYPTION_PASSWORD or asked on the terminal.
+    #[arg(long)]
+    pub decrypt: bool,
+}
+
+impl StreamArgs {
+    /// Print every message of the realtime event stream as it arrives, reconnecting when the connection is lost or a nop is missed.
+    pub fn listen(&self, client: &Client) -> Result<()> {
+        let mut stream = client
+            .stream()
+            .url(&self.url)
+            .nop_timeout(Duration::from_secs(self.nop_timeout));
+        let key = match self.decrypt {
+            true => Some(encryption_key(&client.users().me()?.iden)?),
+            false => None,
+        };
+
+        loop {
+            let error = match stream.read() {
+                Ok(mut message) => {
+                    if let (Some(key), Some(push)) = (&key, &mut message.push) {
+                        match key.decrypt_push(push) {
+                            Ok(decrypted) => *push = decrypted,
+                            Err(error) => eprintln!("Failed to decrypt push: {error}"),
+                        }
+                    }
+                    println!("{}", serde_json::to_string(&message)?);
+                    continue;
+                }
+                Err(error) => error,
+            };
+
+            if matches!(
+                error,
+                PbError::Api {
+                    status: 400..=499,
+                    ..
+                }
+            ) || self.no_reconnect
+            {
+                return Err(error);
+            }
+            eprintln!(
+                "Stream disconnected: {error}, reconnecting in {}s",
+                self.reconnect_delay
+            );
+            thread::sleep(Duration::from_secs(self.reconnect_delay));
+        }
+    }
+}
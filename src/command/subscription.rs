@@ -1,8 +1,17 @@
 use clap::Subcommand;
-use reqwest::blocking::RequestBuilder;
-use serde::{Deserialize, Serialize};
+use pushbullet_rust::{
+    channel::InfoRequest,
+    subscription::{CreateRequest, UpdateRequest},
+    Client, Pagination, Result,
+};
+use serde_json::Value;
 
-use super::{PaginationArgs, Request};
+use super::{
+    collect_pages,
+    guard::{find, GuardArgs},
+    journal::Kind,
+    PaginationArgs, Request,
+};
 
 #[derive(Subcommand)]
 pub enum SubscriptionCommands {
@@ -33,6 +42,9 @@ pub enum SubscriptionCommands {
     Delete {
         /// Unique identifier for this object
         iden: String,
+
+        #[command(flatten)]
+        guard: GuardArgs,
     },
 
     ChannelInfo {
@@ -46,49 +58,30 @@ pub enum SubscriptionCommands {
     },
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct CreateRequest {
-    /// Unique tag for the channel to subscribe to
-    channel_tag: Option<String>,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct UpdateRequest {
-    /// true to mute the grant, false to unmute it
-    muted: Option<bool>,
-}
-
 impl Request for SubscriptionCommands {
-    fn build_request(
-        &self,
-        access_token: &str,
-    ) -> Result<RequestBuilder, Box<dyn std::error::Error>> {
+    fn request(&self, client: &Client) -> Result<Value> {
         match self {
-            SubscriptionCommands::List(args) => {
-                let request_builder = reqwest::blocking::Client::new()
-                    .get("https://api.pushbullet.com/v2/subscriptions")
-                    .query(&args.to_query());
-                Ok(request_builder)
-            }
+            SubscriptionCommands::List(args) if args.all => collect_pages(
+                "subscriptions",
+                client.subscriptions().iter(args.to_request()),
+                args.max_items,
+            ),
+            SubscriptionCommands::List(args) => Ok(serde_json::to_value(
+                client.subscriptions().list(&args.to_request())?,
+            )?),
             SubscriptionCommands::Create {
                 channel_tag,
                 data_binary,
             } => {
                 let request = match data_binary {
-                    Some(data_binary) => match serde_json::from_str(&data_binary) {
-                        Ok(request) => request,
-                        Err(error) => {
-                            return Err(Box::new(error));
-                        }
-                    },
+                    Some(data_binary) => serde_json::from_str(data_binary)?,
                     None => CreateRequest {
                         channel_tag: channel_tag.clone(),
                     },
                 };
-                let request_builder = reqwest::blocking::Client::new()
-                    .post("https://api.pushbullet.com/v2/subscriptions")
-                    .json(&request);
-                Ok(request_builder)
+                Ok(serde_json::to_value(
+                    client.subscriptions().create(&request)?,
+                )?)
             }
             SubscriptionCommands::Update {
                 iden,
@@ -96,46 +89,32 @@ impl Request for SubscriptionCommands {
                 data_binary,
             } => {
                 let request = match data_binary {
-                    Some(data_binary) => match serde_json::from_str(&data_binary) {
-                        Ok(request) => request,
-                        Err(error) => {
-                            return Err(Box::new(error));
-                        }
-                    },
-                    None => UpdateRequest {
-                        muted: muted.clone(),
-                    },
+                    Some(data_binary) => serde_json::from_str(data_binary)?,
+                    None => UpdateRequest { muted: *muted },
                 };
-                let request_builder = reqwest::blocking::Client::new()
-                    .post(format!(
-                        "https://api.pushbullet.com/v2/subscriptions/{}",
-                        iden
-                    ))
-                    .json(&request);
-                Ok(request_builder)
+                Ok(serde_json::to_value(
+                    client.subscriptions().update(iden, &request)?,
+                )?)
             }
-            SubscriptionCommands::Delete { iden } => {
-                let request_builder = reqwest::blocking::Client::new().delete(format!(
-                    "https://api.pushbullet.com/v2/subscriptions/{}",
-                    iden
-                ));
-                Ok(request_builder)
+            SubscriptionCommands::Delete { iden, guard } => {
+                let subscription = find(
+                    Kind::Subscription,
+                    client.subscriptions().iter(Pagination::default()),
+                    iden,
+                )?;
+                guard.delete(client, Kind::Subscription, vec![subscription], |iden| {
+                    client.subscriptions().delete(iden)
+                })
             }
             SubscriptionCommands::ChannelInfo {
                 tag,
                 no_recent_pushed,
             } => {
-                let mut query = vec![];
-                if let Some(tag) = tag {
-                    query.push(tag.to_owned());
-                }
-                if let Some(no_recent_pushed) = no_recent_pushed {
-                    query.push(no_recent_pushed.to_string());
-                }
-                let request_builder = reqwest::blocking::Client::new()
-                    .get("https://api.pushbullet.com/v2/channel-info")
-                    .query(&query);
-                Ok(request_builder)
+                let request = InfoRequest {
+                    tag: tag.clone(),
+                    no_recent_pushes: *no_recent_pushed,
+                };
+                Ok(serde_json::to_value(client.channels().info(&request)?)?)
             }
         }
     }
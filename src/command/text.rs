@@ -1,8 +1,11 @@
 use clap::Subcommand;
-use reqwest::blocking::RequestBuilder;
-use serde::{Deserialize, Serialize};
+use pushbullet_rust::{
+    text::{CreateRequest, Data, UpdateRequest},
+    Client, Result,
+};
+use serde_json::{json, Value};
 
-use super::Request;
+use super::{guard::GuardArgs, journal::Kind, Request};
 
 #[derive(Subcommand)]
 pub enum TextCommands {
@@ -85,56 +88,14 @@ pub enum TextCommands {
     Delete {
         /// Unique identifier for this object
         iden: String,
-    },
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct Data {
-    /// The device_iden of the Device to send the message. This device must have SMS Android permissions granted.
-    target_device_iden: Option<String>,
-
-    /// A list of 1 more phone numbers to send this message to. Including than one number sends a group MMS message.
-    address: Option<String>,
-
-    /// The text content of the text message.
-    message: Option<String>,
-
-    /// Unique identifier optionally set by the client, used to identify a text message to ensure it is not sent multiple times in the case create-text is called for it more than once.
-    guid: Option<String>,
-
-    /// Unique identifier optionally set by the client, used to identify a text message to ensure it is not sent multiple times in the case create-text is called for it more than once.
-    status: Option<String>,
-
-    /// The mime type of the file_url being sent with this message. Only required for messages sending a file.
-    file_type: Option<String>,
-}
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct CreateRequest {
-    /// Map of values specifying this text message.
-    data: Option<Data>,
-
-    /// File download url for an image to send with the text message.
-    file_url: Option<String>,
-
-    /// If set to false, delete the attached file when the Text is deleted.
-    skip_delete_file: Option<bool>,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct UpdateRequest {
-    /// Map of values specifying this text message.
-    data: Option<Data>,
-
-    /// When the text is deleted, don't delete the attached file. The file being deleted or not does not affect the MMS that was sent. Can only be set to true
-    skip_delete_file: Option<bool>,
+        #[command(flatten)]
+        guard: GuardArgs,
+    },
 }
 
 impl Request for TextCommands {
-    fn build_request(
-        &self,
-        access_token: &str,
-    ) -> Result<RequestBuilder, Box<dyn std::error::Error>> {
+    fn request(&self, client: &Client) -> Result<Value> {
         match self {
             TextCommands::Create {
                 target_device_iden,
@@ -148,12 +109,7 @@ impl Request for TextCommands {
                 data_binary,
             } => {
                 let request = match data_binary {
-                    Some(data_binary) => match serde_json::from_str(&data_binary) {
-                        Ok(request) => request,
-                        Err(error) => {
-                            return Err(Box::new(error));
-                        }
-                    },
+                    Some(data_binary) => serde_json::from_str(data_binary)?,
                     None => {
                         let data = Data {
                             target_device_iden: target_device_iden.clone(),
@@ -166,14 +122,11 @@ impl Request for TextCommands {
                         CreateRequest {
                             data: Some(data),
                             file_url: file_url.clone(),
-                            skip_delete_file: skip_delete_file.clone(),
+                            skip_delete_file: *skip_delete_file,
                         }
                     }
                 };
-                let request_builder = reqwest::blocking::Client::new()
-                    .post("https://api.pushbullet.com/v2/texts")
-                    .json(&request);
-                Ok(request_builder)
+                Ok(serde_json::to_value(client.texts().create(&request)?)?)
             }
             TextCommands::Update {
                 iden,
@@ -187,12 +140,7 @@ impl Request for TextCommands {
                 data_binary,
             } => {
                 let request = match data_binary {
-                    Some(data_binary) => match serde_json::from_str(&data_binary) {
-                        Ok(request) => request,
-                        Err(error) => {
-                            return Err(Box::new(error));
-                        }
-                    },
+                    Some(data_binary) => serde_json::from_str(data_binary)?,
                     None => {
                         let data = Data {
                             target_device_iden: target_device_iden.clone(),
@@ -204,19 +152,20 @@ impl Request for TextCommands {
                         };
                         UpdateRequest {
                             data: Some(data),
-                            skip_delete_file: skip_delete_file.clone(),
+                            skip_delete_file: *skip_delete_file,
                         }
                     }
                 };
-                let request_builder = reqwest::blocking::Client::new()
-                    .post(format!("https://api.pushbullet.com/v2/texts/{}", iden))
-                    .json(&request);
-                Ok(request_builder)
+                Ok(serde_json::to_value(
+                    client.texts().update(iden, &request)?,
+                )?)
             }
-            TextCommands::Delete { iden } => {
-                let request_builder = reqwest::blocking::Client::new()
-                    .delete(format!("https://api.pushbullet.com/v2/texts/{}", iden));
-                Ok(request_builder)
+            TextCommands::Delete { iden, guard } => {
+                // Texts cannot be listed, so only their iden is known.
+                let text = json!({ "iden": iden });
+                guard.delete(client, Kind::Text, vec![text], |iden| {
+                    client.texts().delete(iden)
+                })
             }
         }
     }
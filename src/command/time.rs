@@ -0,0 +1,134 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Parse a point in time given on the command line into a unix timestamp: seconds since the epoch such as 1700000000, a duration ago such as 30m, 12h, 7d or 2w, or a UTC date such as 2024-05-01 or 2024-05-01T08:30[:00][Z].
+pub fn parse_time(s: &str) -> Result<f64, String> {
+    let s = s.trim();
+    if let Ok(timestamp) = s.parse::<f64>() {
+        if !timestamp.is_finite() {
+            return Err(format!("expected a finite unix timestamp, got {s}"));
+        }
+        return Ok(timestamp);
+    }
+    if let Some(seconds) = parse_duration(s) {
+        return Ok(now() - seconds);
+    }
+    parse_date(s).ok_or_else(|| {
+        format!("expected a unix timestamp, a duration such as 12h or 7d, or a date such as 2024-05-01T08:30, got {s}")
+    })
+}
+
+/// Seconds since the epoch.
+pub fn now() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs_f64()
+}
+
+fn parse_duration(s: &str) -> Option<f64> {
+    let unit = s.chars().last()?;
+    let seconds = match unit {
+        's' => 1,
+        'm' => 60,
+        'h' => 3600,
+        'd' => 86400,
+        'w' => 7 * 86400,
+        _ => return None,
+    };
+    let amount: u64 = s[..s.len() - 1].parse().ok()?;
+    Some(amount.checked_mul(seconds)? as f64)
+}
+
+fn parse_date(s: &str) -> Option<f64> {
+    let s = s.strip_suffix('Z').unwrap_or(s);
+    let (date, time) = match s.split_once(['T', ' ']) {
+        Some((date, time)) => (date, Some(time)),
+        None => (s, None),
+    };
+
+    let mut date = date.splitn(3, '-').map(str::parse::<i64>);
+    let (year, month, day) = (date.next()?.ok()?, date.next()?.ok()?, date.next()?.ok()?);
+    if !(1..=12).contains(&month) || !(1..=days_in_month(year, month)).contains(&day) {
+        return None;
+    }
+    let mut seconds = days_from_civil(year, month, day) * 86400;
+
+    if let Some(time) = time {
+        let parts: Vec<i64> = time
+            .split(':')
+            .map(str::parse)
+            .collect::<Result<_, _>>()
+            .ok()?;
+        let (hours, minutes, secs) = match parts[..] {
+            [hours, minutes] => (hours, minutes, 0),
+            [hours, minutes, secs] => (hours, minutes, secs),
+            _ => return None,
+        };
+        if !(0..=23).contains(&hours) || !(0..=59).contains(&minutes) || !(0..=60).contains(&secs) {
+            return None;
+        }
+        seconds += hours * 3600 + minutes * 60 + secs;
+    }
+    Some(seconds as f64)
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Days between 1970-01-01 and a date of the proleptic Gregorian calendar.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overflowing_duration_is_rejected() {
+        assert_eq!(parse_duration("2w"), Some(14.0 * 86400.0));
+        assert_eq!(parse_duration("9999999999999999w"), None);
+        assert!(parse_time("9999999999999999w").is_err());
+    }
+
+    #[test]
+    fn negative_date_parts_are_rejected() {
+        assert_eq!(parse_date("2024-01-01T05:00"), Some(1704085200.0));
+        assert_eq!(parse_date("2024-01-01T-5:00"), None);
+        assert_eq!(parse_date("2024-01-01T05:-1"), None);
+        assert_eq!(parse_date("2024-01-01T05:00:-1"), None);
+        assert_eq!(parse_date("2024--1-01"), None);
+        assert_eq!(parse_date("2024-01--1"), None);
+        assert!(parse_time("2024-01-01T-5:00").is_err());
+    }
+
+    #[test]
+    fn days_past_the_end_of_the_month_are_rejected() {
+        assert_eq!(parse_date("2024-02-29"), Some(1709164800.0));
+        assert_eq!(parse_date("2024-02-30"), None);
+        assert_eq!(parse_date("2024-02-31"), None);
+        assert_eq!(parse_date("2023-02-29"), None);
+        assert_eq!(parse_date("2000-02-29"), Some(951782400.0));
+        assert_eq!(parse_date("1900-02-29"), None);
+        assert_eq!(parse_date("2024-04-31"), None);
+    }
+
+    #[test]
+    fn non_finite_timestamps_are_rejected() {
+        assert!(parse_time("NaN").is_err());
+        assert!(parse_time("inf").is_err());
+        assert!(parse_time("-infinity").is_err());
+        assert_eq!(parse_time("1700000000"), Ok(1700000000.0));
+    }
+}
@@ -1,5 +1,6 @@
 use clap::Subcommand;
-use reqwest::blocking::RequestBuilder;
+use pushbullet_rust::{Client, Result};
+use serde_json::Value;
 
 use super::Request;
 
@@ -10,16 +11,9 @@ pub enum UserCommands {
 }
 
 impl Request for UserCommands {
-    fn build_request(
-        &self,
-        access_token: &str,
-    ) -> Result<RequestBuilder, Box<dyn std::error::Error>> {
+    fn request(&self, client: &Client) -> Result<Value> {
         match self {
-            UserCommands::Get => {
-                let request_bulder =
-                    reqwest::blocking::Client::new().get("https://api.pushbullet.com/v2/users/me");
-                Ok(request_bulder)
-            }
+            UserCommands::Get => Ok(serde_json::to_value(client.users().me()?)?),
         }
     }
 }
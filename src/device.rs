@@ -0,0 +1,179 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::{Client, Page, Pagination, Paginator, Result};
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct CreateRequest {
+    /// Name to use when displaying the device
+    /// Example: "Elon Musk's iPhone"
+    pub nickname: Option<String>,
+
+    /// Model of the device
+    /// Example: "iPhone 5s (GSM)"
+    pub model: Option<String>,
+
+    /// Manufacturer of the device
+    /// Example: "Apple"
+    pub manufacturer: Option<String>,
+
+    /// Platform-specific push token. If you are making your own device, leave this blank and you can listen for events on the Realtime Event Stream.
+    /// Example: "production:f73be0ee7877c8c7fa69b1468cde764f"
+    pub push_token: Option<String>,
+
+    /// Version of the Pushbullet application installed on the device
+    /// Example: 8623
+    pub app_version: Option<i32>,
+
+    /// Icon to use for this device, can be an arbitrary string. Commonly used values are: "desktop", "browser", "website", "laptop", "tablet", "phone", "watch", "system"
+    /// Example: "ios"
+    pub icon: Option<String>,
+
+    /// true if the devices has SMS capability, currently only true for type="android" devices
+    /// Example: true
+    pub has_sms: Option<bool>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct UpdateRequest {
+    /// Name to use when displaying the device
+    /// Example: "Elon Musk's iPhone"
+    pub nickname: Option<String>,
+
+    /// Model of the device
+    /// Example: "iPhone 5s (GSM)"
+    pub model: Option<String>,
+
+    /// Manufacturer of the device
+    /// Example: "Apple"
+    pub manufacturer: Option<String>,
+
+    /// Platform-specific push token. If you are making your own device, leave this blank and you can listen for events on the Realtime Event Stream.
+    /// Example: "production:f73be0ee7877c8c7fa69b1468cde764f"
+    pub push_token: Option<String>,
+
+    /// Version of the Pushbullet application installed on the device
+    /// Example: 8623
+    pub app_version: Option<i32>,
+
+    /// Icon to use for this device, can be an arbitrary string. Commonly used values are: "desktop", "browser", "website", "laptop", "tablet", "phone", "watch", "system"
+    /// Example: "ios"
+    pub icon: Option<String>,
+
+    /// true if the devices has SMS capability, currently only true for type="android" devices
+    /// Example: true
+    pub has_sms: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Device {
+    /// Unique identifier for this object
+    pub iden: String,
+
+    /// false if the item has been deleted
+    pub active: bool,
+
+    /// Creation time in floating point seconds (unix timestamp)
+    pub created: f64,
+
+    /// Last modified time in floating point seconds (unix timestamp)
+    pub modified: f64,
+
+    /// Icon to use for this device, can be an arbitrary string. Commonly used values are: "desktop", "browser", "website", "laptop", "tablet", "phone", "watch", "system"
+    pub icon: Option<String>,
+
+    /// Name to use when displaying the device
+    pub nickname: Option<String>,
+
+    /// true if the nickname was automatically generated from the manufacturer and model fields (only used for some android phones)
+    pub generated_nickname: Option<bool>,
+
+    /// Manufacturer of the device
+    pub manufacturer: Option<String>,
+
+    /// Model of the device
+    pub model: Option<String>,
+
+    /// Version of the Pushbullet application installed on the device
+    pub app_version: Option<i32>,
+
+    /// String fingerprint for the device, used by apps to avoid duplicate devices. Value is platform-specific.
+    pub fingerprint: Option<String>,
+
+    /// Fingerprint for the device's end-to-end encryption key, used to determine which devices the current device (based on its own key fingerprint) will be able to talk to.
+    pub key_fingerprint: Option<String>,
+
+    /// Platform-specific push token. If you are making your own device, leave this blank and you can listen for events on the Realtime Event Stream.
+    pub push_token: Option<String>,
+
+    /// true if the devices has SMS capability, currently only true for type="android" devices
+    pub has_sms: Option<bool>,
+
+    /// Fields not known to this client.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListResponse {
+    pub devices: Vec<Device>,
+
+    /// Cursor for the next page of results, absent on the last page.
+    pub cursor: Option<String>,
+
+    /// Fields not known to this client.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl Page for ListResponse {
+    type Item = Device;
+
+    fn cursor(&self) -> Option<&str> {
+        self.cursor.as_deref()
+    }
+
+    fn into_items(self) -> Vec<Device> {
+        self.devices
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct Devices<'a> {
+    client: &'a Client,
+}
+
+impl<'a> Devices<'a> {
+    pub(crate) fn new(client: &'a Client) -> Devices<'a> {
+        Devices { client }
+    }
+
+    /// Get a list of devices belonging to the current user.
+    pub fn list(&self, pagination: &Pagination) -> Result<ListResponse> {
+        self.client.get("/devices", pagination)
+    }
+
+    /// Iterate over every device, following the cursor from page to page.
+    pub fn iter(&self, mut pagination: Pagination) -> Paginator<'a, ListResponse> {
+        let devices = *self;
+        Paginator::new(pagination.cursor.take(), move |cursor| {
+            pagination.cursor = cursor;
+            devices.list(&pagination)
+        })
+    }
+
+    /// Create a new device.
+    pub fn create(&self, request: &CreateRequest) -> Result<Device> {
+        self.client.post("/devices", request)
+    }
+
+    /// Update an existing device.
+    pub fn update(&self, iden: &str, request: &UpdateRequest) -> Result<Device> {
+        self.client.post(&format!("/devices/{}", iden), request)
+    }
+
+    /// Delete a device.
+    pub fn delete(&self, iden: &str) -> Result<()> {
+        self.client.delete(&format!("/devices/{}", iden))
+    }
+}
@@ -0,0 +1,113 @@
+use std::{
+    fs,
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+};
+
+use reqwest::header::CONTENT_LENGTH;
+use tempfile::NamedTempFile;
+
+use crate::{Client, PbError, Progress, Result};
+
+/// How download_file saves a file.
+#[derive(Clone, Default)]
+pub struct DownloadOptions {
+    /// Fail when the number of bytes received differs from the Content-Length of the response, or from this size when it is given.
+    pub verify_size: bool,
+
+    /// Expected size of the file in bytes, when known from elsewhere.
+    pub size: Option<u64>,
+
+    pub progress: Option<Progress>,
+}
+
+impl Client {
+    /// Download the file at file_url into dir as file_name, or as "name (1).ext", "name (2).ext"... when that file already exists. Returns the path of the saved file.
+    pub fn download_file(
+        &self,
+        file_url: &str,
+        dir: &Path,
+        file_name: &str,
+        options: &DownloadOptions,
+    ) -> Result<PathBuf> {
+        let io_error = |e: io::Error| PbError::Io(format!("{}: {e}", dir.display()));
+        // Only the last component, a file name such as "../x" must not escape dir.
+        let file_name = Path::new(file_name)
+            .file_name()
+            .and_then(|name| name.to_str())
+            .filter(|name| !name.is_empty())
+            .unwrap_or("download");
+
+        // The file url belongs to the file storage, not to the API, so the access token is not sent.
+        let mut response = self.http.get(file_url).send()?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(PbError::Api {
+                status: status.as_u16(),
+                error: None,
+            });
+        }
+        let length = response
+            .headers()
+            .get(CONTENT_LENGTH)
+            .and_then(|length| length.to_str().ok())
+            .and_then(|length| length.parse::<u64>().ok());
+        let total = options.size.or(length).unwrap_or(0);
+
+        fs::create_dir_all(dir).map_err(io_error)?;
+        // Written next to its destination first, so an interrupted download never leaves a partial file under the final name.
+        let mut file = NamedTempFile::new_in(dir).map_err(io_error)?;
+        let mut received = 0;
+        let mut buf = vec![0; 64 * 1024];
+        loop {
+            let read = response
+                .read(&mut buf)
+                .map_err(|e| PbError::Io(format!("{file_name}: download interrupted: {e}")))?;
+            if read == 0 {
+                break;
+            }
+            file.write_all(&buf[..read]).map_err(io_error)?;
+            received += read as u64;
+            if let Some(progress) = &options.progress {
+                progress(received, total.max(received));
+            }
+        }
+
+        if options.verify_size {
+            for expected in [length, options.size].into_iter().flatten() {
+                if received != expected {
+                    return Err(PbError::Io(format!(
+                        "{file_name}: received {received} bytes, expected {expected}"
+                    )));
+                }
+            }
+        }
+        persist(file, dir, file_name)
+    }
+}
+
+/// Move file to dir/file_name without replacing an existing file, numbering the name until a free one is found.
+fn persist(mut file: NamedTempFile, dir: &Path, file_name: &str) -> Result<PathBuf> {
+    let name = Path::new(file_name);
+    let stem = name
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let extension = name
+        .extension()
+        .map(|extension| format!(".{}", extension.to_string_lossy()))
+        .unwrap_or_default();
+
+    for n in 0.. {
+        let path = match n {
+            0 => dir.join(file_name),
+            _ => dir.join(format!("{stem} ({n}){extension}")),
+        };
+        match file.persist_noclobber(&path) {
+            Ok(_) => return Ok(path),
+            Err(e) if e.error.kind() == io::ErrorKind::AlreadyExists => file = e.file,
+            Err(e) => return Err(PbError::Io(format!("{}: {}", path.display(), e.error))),
+        }
+    }
+    unreachable!()
+}
@@ -0,0 +1,132 @@
+use aes_gcm::{
+    aead::{Aead, AeadCore, KeyInit, OsRng},
+    Aes256Gcm, Key, Nonce,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::Sha256;
+
+use crate::{Client, PbError, Result};
+
+/// Iterations of PBKDF2 used by the Pushbullet apps to derive the encryption key.
+const PBKDF2_ROUNDS: u32 = 30000;
+
+/// Version byte at the start of encrypted messages.
+const VERSION: u8 = b'1';
+
+/// Ephemeral pushes understood by the Pushbullet apps.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum EphemeralPush {
+    /// Content of the clipboard, copied to the devices with universal copy/paste enabled.
+    Clip {
+        /// Text copied to the clipboard
+        body: String,
+
+        /// User iden of the sender
+        source_user_iden: String,
+
+        /// Device iden of the device the clip comes from
+        #[serde(skip_serializing_if = "Option::is_none")]
+        source_device_iden: Option<String>,
+    },
+
+    /// Dismissal of a notification mirrored from an Android device, hiding it on the other devices.
+    Dismissal {
+        /// Package name of the app that posted the notification
+        package_name: String,
+
+        /// Android id of the notification
+        notification_id: String,
+
+        /// Android tag of the notification
+        #[serde(skip_serializing_if = "Option::is_none")]
+        notification_tag: Option<String>,
+
+        /// User iden of the sender
+        source_user_iden: String,
+    },
+}
+
+/// Key for the end-to-end encryption of ephemerals, derived from the password set in the Pushbullet apps.
+pub struct EncryptionKey(Key<Aes256Gcm>);
+
+impl EncryptionKey {
+    /// PBKDF2-HMAC-SHA256 of password salted with the iden of the user, as the Pushbullet apps derive it.
+    pub fn derive(password: &str, user_iden: &str) -> EncryptionKey {
+        let mut key = Key::<Aes256Gcm>::default();
+        pbkdf2::pbkdf2_hmac::<Sha256>(
+            password.as_bytes(),
+            user_iden.as_bytes(),
+            PBKDF2_ROUNDS,
+            &mut key,
+        );
+        EncryptionKey(key)
+    }
+
+    /// AES-256-GCM encryption of plaintext, base64 of the version byte, the tag, the random IV and the ciphertext.
+    pub fn encrypt(&self, plaintext: &[u8]) -> String {
+        let iv = Aes256Gcm::generate_nonce(&mut OsRng);
+        let mut encrypted = Aes256Gcm::new(&self.0)
+            .encrypt(&iv, plaintext)
+            .expect("plaintext fits in AES-GCM");
+        // aes-gcm appends the tag to the ciphertext, Pushbullet puts it first.
+        let tag = encrypted.split_off(encrypted.len() - 16);
+        let mut message = vec![VERSION];
+        message.extend_from_slice(&tag);
+        message.extend_from_slice(&iv);
+        message.extend_from_slice(&encrypted);
+        STANDARD.encode(message)
+    }
+
+    /// Plaintext of a message encrypted by encrypt or by the Pushbullet apps.
+    pub fn decrypt(&self, message: &str) -> Result<Vec<u8>> {
+        let decrypt_error = || PbError::Config(String::from("cannot decrypt, wrong password?"));
+        let message = STANDARD.decode(message).map_err(|_| decrypt_error())?;
+        if message.len() < 1 + 16 + 12 || message[0] != VERSION {
+            return Err(decrypt_error());
+        }
+        let (tag, rest) = message[1..].split_at(16);
+        let (iv, ciphertext) = rest.split_at(12);
+        let mut encrypted = ciphertext.to_vec();
+        encrypted.extend_from_slice(tag);
+        Aes256Gcm::new(&self.0)
+            .decrypt(Nonce::from_slice(iv), encrypted.as_slice())
+            .map_err(|_| decrypt_error())
+    }
+
+    /// The ephemeral push an encrypted one stands for, others being returned as they are.
+    pub fn decrypt_push(&self, push: &Value) -> Result<Value> {
+        match push["ciphertext"].as_str() {
+            Some(ciphertext) if push["encrypted"] == true => {
+                Ok(serde_json::from_slice(&self.decrypt(ciphertext)?)?)
+            }
+            _ => Ok(push.clone()),
+        }
+    }
+}
+
+pub struct Ephemerals<'a> {
+    client: &'a Client,
+}
+
+impl<'a> Ephemerals<'a> {
+    pub(crate) fn new(client: &'a Client) -> Ephemerals<'a> {
+        Ephemerals { client }
+    }
+
+    /// Send an ephemeral push to every device of the current user, without storing it. With key it is end-to-end encrypted.
+    pub fn send<P>(&self, push: &P, key: Option<&EncryptionKey>) -> Result<Value>
+    where
+        P: Serialize + ?Sized,
+    {
+        let mut push = serde_json::to_value(push)?;
+        if let Some(key) = key {
+            let ciphertext = key.encrypt(&serde_json::to_vec(&push)?);
+            push = json!({ "encrypted": true, "ciphertext": ciphertext });
+        }
+        self.client
+            .post("/ephemerals", &json!({ "type": "push", "push": push }))
+    }
+}
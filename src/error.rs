@@ -0,0 +1,105 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+pub type Result<T> = std::result::Result<T, PbError>;
+
+/// Error returned by every call of the client.
+#[derive(Debug)]
+pub enum PbError {
+    /// The request could not be sent or the response could not be read.
+    Transport(reqwest::Error),
+
+    /// The server answered with a non-success status, with the error body when it sent one.
+    Api {
+        status: u16,
+        error: Option<ApiError>,
+    },
+
+    /// A JSON body could not be encoded or decoded.
+    Decode(serde_json::Error),
+
+    /// The configuration or the access token could not be read or written.
+    Config(String),
+
+    /// A file could not be read or uploaded.
+    Upload(String),
+
+    /// The realtime event stream could not be connected or was lost.
+    Stream(String),
+
+    /// Local input or output failed, such as reading stdin or writing a file.
+    Io(String),
+}
+
+/// Error body sent by Pushbullet along with a non-success status.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiError {
+    /// Machine readable error code, for example "invalid_access_token"
+    pub code: Option<String>,
+
+    /// Kind of error, for example "invalid_request" or "server"
+    #[serde(rename = "type")]
+    pub t: Option<String>,
+
+    /// Human readable description of the error
+    pub message: Option<String>,
+
+    /// Documentation link about the error
+    pub cite: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct ErrorResponse {
+    pub error: ApiError,
+}
+
+impl fmt::Display for PbError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PbError::Transport(e) => write!(f, "request failed: {e}"),
+            PbError::Api { status, error } => {
+                write!(f, "server responded with status {status}")?;
+                if let Some(error) = error {
+                    if let Some(t) = &error.t {
+                        write!(f, " ({t})")?;
+                    }
+                    if let Some(message) = &error.message {
+                        write!(f, ": {message}")?;
+                    }
+                    if let Some(cite) = &error.cite {
+                        write!(f, ", see {cite}")?;
+                    }
+                }
+                Ok(())
+            }
+            PbError::Decode(e) => write!(f, "invalid JSON: {e}"),
+            PbError::Config(message) => write!(f, "config error: {message}"),
+            PbError::Upload(message) => write!(f, "upload failed: {message}"),
+            PbError::Stream(message) => write!(f, "stream error: {message}"),
+            PbError::Io(message) => write!(f, "I/O error: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for PbError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            PbError::Transport(e) => Some(e),
+            PbError::Decode(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for PbError {
+    fn from(e: reqwest::Error) -> PbError {
+        PbError::Transport(e)
+    }
+}
+
+impl From<serde_json::Error> for PbError {
+    fn from(e: serde_json::Error) -> PbError {
+        PbError::Decode(e)
+    }
+}
@@ -0,0 +1,36 @@
+//! Client for the [Pushbullet](https://docs.pushbullet.com) HTTP API.
+//!
+//! ```no_run
+//! use pushbullet_rust::{push::CreateRequest, Client};
+//!
+//! let client = Client::new("o.xxxxxxxx");
+//! let push = client.pushes().create(&CreateRequest {
+//!     t: Some("note".to_owned()),
+//!     title: Some("Hello".to_owned()),
+//!     ..Default::default()
+//! })?;
+//! println!("{}", push.iden);
+//! # Ok::<(), pushbullet_rust::PbError>(())
+//! ```
+
+pub mod channel;
+pub mod chat;
+mod client;
+pub mod device;
+mod download;
+pub mod ephemeral;
+pub mod error;
+pub mod mime;
+mod pagination;
+pub mod push;
+pub mod stream;
+pub mod subscription;
+pub mod text;
+mod upload;
+pub mod user;
+
+pub use client::*;
+pub use download::*;
+pub use error::{PbError, Result};
+pub use pagination::*;
+pub use upload::*;
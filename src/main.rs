@@ -1,50 +1,121 @@
+use std::{process, time::Duration};
+
 use clap::Parser;
-use command::{read_access_token, set_access_token, Cli, Commands::*, Request};
+use command::{
+    exit_code, read_access_token, read_stdin_token, set_access_token, set_api_url, strip_nulls,
+    Cache, CacheCommands, Cli, Commands::*, Output, OutputFormat, Request, Resolver,
+};
+use pushbullet_rust::{Client, Result};
 
 mod command;
 
 fn main() {
     let cli = Cli::parse();
 
-    if let AccessToken { access_token } = cli.command {
-        if let Err(e) = set_access_token(&access_token) {
-            panic!("Set access token error: {e:?}");
+    if let Err(e) = run(cli) {
+        eprintln!("Error: {e}");
+        process::exit(exit_code(&e));
+    }
+}
+
+fn run(mut cli: Cli) -> Result<()> {
+    // The commands writing settings read an invalid config as an empty one, so that it can be repaired.
+    let config = match cli.command {
+        AccessToken { .. } | ApiUrl { .. } | Config(_) => command::Config::load_for_update()?,
+        _ => command::Config::load()?,
+    };
+    let profile_name = config.profile_name(cli.profile.as_deref());
+    command::check_profile(&profile_name)?;
+    match &cli.command {
+        AccessToken {
+            access_token,
+            keyring,
+            ..
+        } => {
+            let access_token = match access_token {
+                Some(access_token) => access_token.clone(),
+                None => read_stdin_token()?,
+            };
+            return set_access_token(config, &profile_name, &access_token, *keyring);
         }
-    } else {
-        let access_token = read_access_token().unwrap_or_else(|e| {
-            panic!("{e:?}");
-        });
+        ApiUrl { api_url, .. } => return set_api_url(config, &profile_name, api_url.as_deref()),
+        Config(config_commands) => return config_commands.run(config, &profile_name),
+        _ => (),
+    }
 
-        match cli.command {
-            Chat(chat_commands) => match chat_commands.request(&access_token) {
-                Ok(res) => println!("{res}"),
-                Err(e) => panic!("{e:?}"),
-            },
-            Device(device_commands) => match device_commands.request(&access_token) {
-                Ok(res) => println!("{res}"),
-                Err(e) => panic!("{e:?}"),
-            },
-            Push(push_commands) => match push_commands.request(&access_token) {
-                Ok(res) => println!("{res}"),
-                Err(e) => panic!("{e:?}"),
-            },
-            Channel(channel_commands) => match channel_commands.request(&access_token) {
-                Ok(res) => println!("{res}"),
-                Err(e) => panic!("{e:?}"),
-            },
-            Subscription(subscription_commands) => match subscription_commands.request(&access_token) {
-                Ok(res) => println!("{res}"),
-                Err(e) => panic!("{e:?}"),
-            },
-            Text(text_commands) => match text_commands.request(&access_token) {
-                Ok(res) => println!("{res}"),
-                Err(e) => panic!("{e:?}"),
-            },
-            User(user_commands) => match user_commands.request(&access_token) {
-                Ok(res) => println!("{res}"),
-                Err(e) => panic!("{e:?}"),
-            },
-            _ => (),
+    let profile = config.profile(&profile_name);
+    let format = match (cli.output, &cli.template, &profile.output) {
+        (Some(format), _, _) => format,
+        (None, Some(_), _) => OutputFormat::Template,
+        (None, None, Some(output)) => OutputFormat::parse(output)?,
+        (None, None, None) => OutputFormat::Json,
+    };
+    let output = Output {
+        format,
+        template: cli.template.clone(),
+        columns: cli.columns.clone(),
+    };
+    let cache = Cache::open(&profile_name)?;
+    if cli.offline
+        || matches!(
+            cli.command,
+            Cache(CacheCommands::Status | CacheCommands::Clear)
+        )
+    {
+        cli.command.resolve(&mut Resolver::new(None, &cache))?;
+        let mut res = cli.command.offline(&cache)?;
+        strip_nulls(&mut res);
+        return output.print(&res);
+    }
+    let access_token = read_access_token(&profile_name, &profile, cli.token.as_deref())?;
+    let mut client = Client::new(&access_token).with_timeouts(
+        profile.timeout.map(Duration::from_secs),
+        profile.connect_timeout.map(Duration::from_secs),
+    )?;
+    if let Some(api_url) = cli.api_url.or(profile.api_url) {
+        client = client.with_api_url(&api_url);
+    }
+    if let Some(guard) = cli.command.guard_mut() {
+        guard.profile = profile_name.clone();
+    }
+    match (&mut cli.command, &profile.device) {
+        (Push(push_commands), Some(device)) => push_commands.set_default_device(device),
+        (Send(send_args), Some(device)) => send_args.target.set_default_device(device),
+        (Pipe(pipe_args), Some(device)) => pipe_args.target.set_default_device(device),
+        (Exec(exec_args), Some(device)) => exec_args.target.set_default_device(device),
+        _ => (),
+    }
+    match (&mut cli.command, &profile.source_device) {
+        (Push(push_commands), Some(device)) => push_commands.set_default_source(device),
+        (Ephemeral(ephemeral_commands), Some(device)) => {
+            ephemeral_commands.set_default_source(device)
         }
+        (Send(send_args), Some(device)) => send_args.target.set_default_source(device),
+        (Pipe(pipe_args), Some(device)) => pipe_args.target.set_default_source(device),
+        (Exec(exec_args), Some(device)) => exec_args.target.set_default_source(device),
+        _ => (),
     }
+    let mut resolver = Resolver::new(Some(&client), &cache);
+    cli.command.resolve(&mut resolver)?;
+
+    let mut res = match cli.command {
+        Chat(chat_commands) => chat_commands.request(&client)?,
+        Device(device_commands) => device_commands.run(&client, &profile_name)?,
+        Push(push_commands) => push_commands.request(&client)?,
+        Send(send_args) => send_args.request(&client)?,
+        Pipe(pipe_args) => pipe_args.request(&client)?,
+        Channel(channel_commands) => channel_commands.request(&client)?,
+        Subscription(subscription_commands) => subscription_commands.request(&client)?,
+        Text(text_commands) => text_commands.request(&client)?,
+        User(user_commands) => user_commands.request(&client)?,
+        Ephemeral(ephemeral_commands) => ephemeral_commands.request(&client)?,
+        Stream(stream_args) => return stream_args.listen(&client),
+        Daemon(daemon_args) => return daemon_args.run(&client, &profile_name, &mut resolver),
+        Exec(exec_args) => process::exit(exec_args.run(&client)?),
+        Undo(undo_args) => undo_args.run(&client, &profile_name)?,
+        Cache(cache_commands) => cache_commands.run(Some(&client), &cache)?,
+        AccessToken { .. } | ApiUrl { .. } | Config(_) => unreachable!(),
+    };
+    strip_nulls(&mut res);
+    output.print(&res)
 }
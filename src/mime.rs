@@ -0,0 +1,191 @@
+//! MIME type detection of local files from their content and extension, without depending on libmagic unless the `libmagic` feature is enabled.
+
+use std::{fs::File, io::Read, path::Path};
+
+/// Type of files nothing is known about.
+pub const DEFAULT_TYPE: &str = "application/octet-stream";
+
+/// Leading bytes of known formats, with the offset they start at.
+const SIGNATURES: &[(usize, &[u8], &str)] = &[
+    (0, b"\x89PNG\r\n\x1a\n", "image/png"),
+    (0, b"\xff\xd8\xff", "image/jpeg"),
+    (0, b"GIF87a", "image/gif"),
+    (0, b"GIF89a", "image/gif"),
+    (0, b"II*\0", "image/tiff"),
+    (0, b"MM\0*", "image/tiff"),
+    (0, b"\0\0\x01\0", "image/x-icon"),
+    (0, b"%PDF-", "application/pdf"),
+    (0, b"%!PS", "application/postscript"),
+    (0, b"{\\rtf", "application/rtf"),
+    (0, b"PK\x03\x04", "application/zip"),
+    (0, b"\x1f\x8b", "application/gzip"),
+    (0, b"BZh", "application/x-bzip2"),
+    (0, b"\xfd7zXZ\0", "application/x-xz"),
+    (0, b"7z\xbc\xaf\x27\x1c", "application/x-7z-compressed"),
+    (0, b"Rar!\x1a\x07", "application/vnd.rar"),
+    (257, b"ustar", "application/x-tar"),
+    (0, b"SQLite format 3\0", "application/vnd.sqlite3"),
+    (0, b"\0asm", "application/wasm"),
+    (0, b"\x7fELF", "application/x-executable"),
+    (0, b"ID3", "audio/mpeg"),
+    (0, b"\xff\xfb", "audio/mpeg"),
+    (0, b"OggS", "audio/ogg"),
+    (0, b"fLaC", "audio/flac"),
+    (0, b"\x1a\x45\xdf\xa3", "video/x-matroska"),
+];
+
+/// Types from file extensions, for text formats and for formats sharing a container such as zip.
+const EXTENSIONS: &[(&str, &str)] = &[
+    ("txt", "text/plain"),
+    ("log", "text/plain"),
+    ("md", "text/markdown"),
+    ("csv", "text/csv"),
+    ("tsv", "text/tab-separated-values"),
+    ("html", "text/html"),
+    ("htm", "text/html"),
+    ("css", "text/css"),
+    ("js", "text/javascript"),
+    ("json", "application/json"),
+    ("xml", "application/xml"),
+    ("yaml", "application/yaml"),
+    ("yml", "application/yaml"),
+    ("toml", "application/toml"),
+    ("svg", "image/svg+xml"),
+    ("ics", "text/calendar"),
+    ("vcf", "text/vcard"),
+    ("png", "image/png"),
+    ("jpg", "image/jpeg"),
+    ("jpeg", "image/jpeg"),
+    ("gif", "image/gif"),
+    ("webp", "image/webp"),
+    ("bmp", "image/bmp"),
+    ("tif", "image/tiff"),
+    ("tiff", "image/tiff"),
+    ("ico", "image/x-icon"),
+    ("heic", "image/heic"),
+    ("pdf", "application/pdf"),
+    ("zip", "application/zip"),
+    ("gz", "application/gzip"),
+    ("tar", "application/x-tar"),
+    ("7z", "application/x-7z-compressed"),
+    ("rar", "application/vnd.rar"),
+    (
+        "docx",
+        "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+    ),
+    (
+        "xlsx",
+        "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+    ),
+    (
+        "pptx",
+        "application/vnd.openxmlformats-officedocument.presentationml.presentation",
+    ),
+    ("odt", "application/vnd.oasis.opendocument.text"),
+    ("ods", "application/vnd.oasis.opendocument.spreadsheet"),
+    ("epub", "application/epub+zip"),
+    ("apk", "application/vnd.android.package-archive"),
+    ("jar", "application/java-archive"),
+    ("mp3", "audio/mpeg"),
+    ("m4a", "audio/mp4"),
+    ("wav", "audio/wav"),
+    ("ogg", "audio/ogg"),
+    ("flac", "audio/flac"),
+    ("mp4", "video/mp4"),
+    ("m4v", "video/mp4"),
+    ("mov", "video/quicktime"),
+    ("mkv", "video/x-matroska"),
+    ("webm", "video/webm"),
+    ("avi", "video/x-msvideo"),
+];
+
+/// MIME type of a file, from libmagic when the `libmagic` feature is enabled and it works, otherwise from its leading bytes and its extension.
+pub fn detect(path: &Path) -> String {
+    #[cfg(feature = "libmagic")]
+    if let Some(file_type) = libmagic(path) {
+        return file_type;
+    }
+
+    let mut head = Vec::with_capacity(512);
+    if let Ok(file) = File::open(path) {
+        let _ = file.take(512).read_to_end(&mut head);
+    }
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(str::to_ascii_lowercase);
+    sniff(&head, extension.as_deref()).to_owned()
+}
+
+/// MIME type from the leading bytes of a file and its lowercase extension.
+pub fn sniff(head: &[u8], extension: Option<&str>) -> &'static str {
+    let by_extension = extension.and_then(|extension| {
+        EXTENSIONS
+            .iter()
+            .find(|(known, _)| *known == extension)
+            .map(|(_, file_type)| *file_type)
+    });
+
+    if let Some(file_type) = riff(head).or_else(|| iso_media(head)) {
+        return file_type;
+    }
+    let signature = SIGNATURES
+        .iter()
+        .find(|(offset, signature, _)| {
+            head.get(*offset..*offset + signature.len()) == Some(signature)
+        })
+        .map(|(_, _, file_type)| *file_type);
+    match (signature, by_extension) {
+        // Office documents, epub, apk and jar are all zip files.
+        (Some("application/zip"), Some(file_type)) => file_type,
+        (Some("video/x-matroska"), Some("video/webm")) => "video/webm",
+        (Some(file_type), _) => file_type,
+        (None, Some(file_type)) => file_type,
+        (None, None) if is_text(head) => "text/plain",
+        (None, None) => DEFAULT_TYPE,
+    }
+}
+
+/// WebP, WAV and AVI files are RIFF containers named at offset 8.
+fn riff(head: &[u8]) -> Option<&'static str> {
+    if head.get(0..4) != Some(b"RIFF") {
+        return None;
+    }
+    match head.get(8..12)? {
+        b"WEBP" => Some("image/webp"),
+        b"WAVE" => Some("audio/wav"),
+        b"AVI " => Some("video/x-msvideo"),
+        _ => None,
+    }
+}
+
+/// MP4, QuickTime and HEIC files start with an ftyp box naming their brand.
+fn iso_media(head: &[u8]) -> Option<&'static str> {
+    if head.get(4..8) != Some(b"ftyp") {
+        return None;
+    }
+    match head.get(8..12)? {
+        b"qt  " => Some("video/quicktime"),
+        b"M4A " => Some("audio/mp4"),
+        b"heic" | b"heix" | b"mif1" => Some("image/heic"),
+        _ => Some("video/mp4"),
+    }
+}
+
+/// UTF-8 without NUL bytes, allowing a character cut at the end of head.
+fn is_text(head: &[u8]) -> bool {
+    if head.is_empty() || head.contains(&0) {
+        return false;
+    }
+    match std::str::from_utf8(head) {
+        Ok(_) => true,
+        Err(e) => e.error_len().is_none() && e.valid_up_to() + 4 > head.len(),
+    }
+}
+
+#[cfg(feature = "libmagic")]
+fn libmagic(path: &Path) -> Option<String> {
+    let cookie = magic::Cookie::open(magic::cookie::Flags::MIME_TYPE).ok()?;
+    let cookie = cookie.load(&Default::default()).ok()?;
+    cookie.file(path).ok()
+}
@@ -0,0 +1,89 @@
+use std::vec;
+
+use serde::Serialize;
+
+use crate::Result;
+
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct Pagination {
+    /// Request objects modified after this timestamp, including deleted ones with active set to false.
+    pub modified_after: Option<String>,
+
+    /// When listing objects, if you receive a cursor in the response, it means the results are on multiple pages. To request the next page of results, use this cursor as the parameter cursor in the next request.
+    pub cursor: Option<String>,
+
+    /// You can specify a limit parameter that return a list of objects to get a smaller number of objects on each page.
+    pub limit: Option<i32>,
+}
+
+/// One page of a list endpoint.
+pub trait Page {
+    type Item;
+
+    /// Cursor for the next page, None on the last page.
+    fn cursor(&self) -> Option<&str>;
+
+    fn into_items(self) -> Vec<Self::Item>;
+}
+
+/// Iterator over every object of a list endpoint, requesting the next page whenever the current one is exhausted.
+pub struct Paginator<'a, P: Page> {
+    fetch: Box<dyn FnMut(Option<String>) -> Result<P> + 'a>,
+    cursor: Option<String>,
+    items: vec::IntoIter<P::Item>,
+    done: bool,
+}
+
+impl<'a, P: Page> Paginator<'a, P> {
+    /// Start at the page of cursor, or at the first page when there is none.
+    pub(crate) fn new<F>(cursor: Option<String>, fetch: F) -> Paginator<'a, P>
+    where
+        F: FnMut(Option<String>) -> Result<P> + 'a,
+    {
+        Paginator {
+            fetch: Box::new(fetch),
+            cursor,
+            items: Vec::new().into_iter(),
+            done: false,
+        }
+    }
+
+    /// Cursor of the page after the objects already buffered, to resume listing later.
+    pub fn cursor(&self) -> Option<&str> {
+        self.cursor.as_deref()
+    }
+
+    /// false once every object has been returned.
+    pub fn has_more(&self) -> bool {
+        self.items.len() > 0 || !self.done
+    }
+}
+
+impl<P: Page> Iterator for Paginator<'_, P> {
+    type Item = Result<P::Item>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(item) = self.items.next() {
+                return Some(Ok(item));
+            }
+            if self.done {
+                return None;
+            }
+
+            match (self.fetch)(self.cursor.take()) {
+                Ok(page) => {
+                    self.cursor = page.cursor().map(str::to_owned);
+                    let items = page.into_items();
+                    // An empty page with a cursor would otherwise be requested forever.
+                    self.done = self.cursor.is_none() || items.is_empty();
+                    self.items = items.into_iter();
+                }
+                Err(error) => {
+                    self.done = true;
+                    return Some(Err(error));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,232 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::{Client, Page, Paginator, Result};
+
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct ListRequest {
+    /// Request pushes modified after this timestamp
+    pub modified_after: Option<String>,
+
+    /// Don't return deleted pushes
+    pub active: Option<bool>,
+
+    /// When listing objects, if you receive a cursor in the response, it means the results are on multiple pages. To request the next page of results, use this cursor as the parameter cursor in the next request.
+    pub cursor: Option<String>,
+
+    /// You can specify a limit parameter that return a list of objects to get a smaller number of objects on each page.
+    pub limit: Option<i32>,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct CreateRequest {
+    /// Type of the push, one of "note", "file", "link".
+    #[serde(rename = "type")]
+    pub t: Option<String>,
+
+    /// Title of the push, used for all types of pushes
+    pub title: Option<String>,
+
+    /// Body of the push, used for all types of pushes
+    pub body: Option<String>,
+
+    /// URL field, used for type="link" pushes
+    pub url: Option<String>,
+
+    /// File name, used for type="file" pushes
+    pub file_name: Option<String>,
+
+    /// File mime type, used for type="file" pushes
+    pub file_type: Option<String>,
+
+    /// File download url, used for type="file" pushes
+    pub file_url: Option<String>,
+
+    /// Device iden of the sending device. Optional.
+    pub source_device_iden: Option<String>,
+
+    /// Device iden of the target device, if sending to a single device. Appears as target_device_iden on the push.
+    pub device_iden: Option<String>,
+
+    /// Client iden of the target client, sends a push to all users who have granted access to this client. The current user must own this client.
+    pub client_iden: Option<String>,
+
+    /// Channel tag of the target channel, sends a push to all people who are subscribed to this channel. The current user must own this channel.
+    pub channel_tag: Option<String>,
+
+    /// Email address to send the push to. If there is a pushbullet user with this address, they get a push, otherwise they get an email.
+    pub email: Option<String>,
+
+    /// Unique identifier set by the client, used to identify a push in case you receive it from /v2/everything before the call to /v2/pushes has completed. This should be a unique value. Pushes with guid set are mostly idempotent, meaning that sending another push with the same guid is unlikely to create another push (it will return the previously created push).
+    pub guid: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct UpdateRequest {
+    /// Marks a push as having been dismissed by the user, will cause any notifications for the push to be hidden if possible.
+    pub dismissed: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Push {
+    /// Unique identifier for this object
+    pub iden: String,
+
+    /// false if the item has been deleted
+    pub active: bool,
+
+    /// Creation time in floating point seconds (unix timestamp)
+    pub created: f64,
+
+    /// Last modified time in floating point seconds (unix timestamp)
+    pub modified: f64,
+
+    /// Type of the push, one of "note", "file", "link".
+    #[serde(rename = "type")]
+    pub t: Option<String>,
+
+    /// true if the push has been dismissed by any device or if any device was active when the push was received
+    pub dismissed: Option<bool>,
+
+    /// Unique identifier set by the client, used to identify a push in case you receive it from /v2/everything before the call to /v2/pushes has completed.
+    pub guid: Option<String>,
+
+    /// Direction the push was sent in, can be "self", "outgoing", or "incoming"
+    pub direction: Option<String>,
+
+    /// User iden of the sender
+    pub sender_iden: Option<String>,
+
+    /// Email address of the sender
+    pub sender_email: Option<String>,
+
+    /// Canonical email address of the sender
+    pub sender_email_normalized: Option<String>,
+
+    /// Name of the sender
+    pub sender_name: Option<String>,
+
+    /// User iden of the receiver
+    pub receiver_iden: Option<String>,
+
+    /// Email address of the receiver
+    pub receiver_email: Option<String>,
+
+    /// Canonical email address of the receiver
+    pub receiver_email_normalized: Option<String>,
+
+    /// Device iden of the target device, if sending to a single device
+    pub target_device_iden: Option<String>,
+
+    /// Device iden of the sending device. Optionally set by the sender when creating a push
+    pub source_device_iden: Option<String>,
+
+    /// If the push was created by a client, set to the iden of that client.
+    pub client_iden: Option<String>,
+
+    /// If the push was created by a channel, set to the iden of that channel
+    pub channel_iden: Option<String>,
+
+    /// List of guids (client side identifiers) for the apps that were awake when the push was received
+    pub awake_app_guids: Option<Vec<String>>,
+
+    /// Title of the push, used for all types of pushes
+    pub title: Option<String>,
+
+    /// Body of the push, used for all types of pushes
+    pub body: Option<String>,
+
+    /// URL field, used for type="link" pushes
+    pub url: Option<String>,
+
+    /// File name, used for type="file" pushes
+    pub file_name: Option<String>,
+
+    /// File mime type, used for type="file" pushes
+    pub file_type: Option<String>,
+
+    /// File download url, used for type="file" pushes
+    pub file_url: Option<String>,
+
+    /// URL to an image to use for this push, present on type="file" pushes if file_type matches image/*
+    pub image_url: Option<String>,
+
+    /// Width of image in pixels, only present if image_url is set
+    pub image_width: Option<i64>,
+
+    /// Height of image in pixels, only present if image_url is set
+    pub image_height: Option<i64>,
+
+    /// Fields not known to this client.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListResponse {
+    pub pushes: Vec<Push>,
+
+    /// Cursor for the next page of results, absent on the last page.
+    pub cursor: Option<String>,
+
+    /// Fields not known to this client.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl Page for ListResponse {
+    type Item = Push;
+
+    fn cursor(&self) -> Option<&str> {
+        self.cursor.as_deref()
+    }
+
+    fn into_items(self) -> Vec<Push> {
+        self.pushes
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct Pushes<'a> {
+    client: &'a Client,
+}
+
+impl<'a> Pushes<'a> {
+    pub(crate) fn new(client: &'a Client) -> Pushes<'a> {
+        Pushes { client }
+    }
+
+    /// Request push history.
+    pub fn list(&self, request: &ListRequest) -> Result<ListResponse> {
+        self.client.get("/pushes", request)
+    }
+
+    /// Iterate over every push of the push history, following the cursor from page to page.
+    pub fn iter(&self, mut request: ListRequest) -> Paginator<'a, ListResponse> {
+        let pushes = *self;
+        Paginator::new(request.cursor.take(), move |cursor| {
+            request.cursor = cursor;
+            pushes.list(&request)
+        })
+    }
+
+    /// Send a push to a device or another person.
+    pub fn create(&self, request: &CreateRequest) -> Result<Push> {
+        self.client.post("/pushes", request)
+    }
+
+    /// Update a push.
+    pub fn update(&self, iden: &str, request: &UpdateRequest) -> Result<Push> {
+        self.client.post(&format!("/pushes/{}", iden), request)
+    }
+
+    /// Delete a push.
+    pub fn delete(&self, iden: &str) -> Result<()> {
+        self.client.delete(&format!("/pushes/{}", iden))
+    }
+
+    /// Delete all pushes belonging to the current user. This call is asynchronous, the pushes will be deleted after the call returns.
+    pub fn delete_all(&self) -> Result<()> {
+        self.client.delete("/pushes")
+    }
+}
@@ -0,0 +1,120 @@
+use std::{io::ErrorKind, net::TcpStream, time::Duration};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tungstenite::{stream::MaybeTlsStream, Message, WebSocket};
+
+use crate::{PbError, Result};
+
+/// Endpoint of the realtime event stream.
+pub const STREAM_URL: &str = "wss://stream.pushbullet.com/websocket";
+
+/// A message received from the realtime event stream.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StreamMessage {
+    /// Type of the message, one of "nop", "tickle", "push".
+    #[serde(rename = "type")]
+    pub t: String,
+
+    /// For type="tickle" messages, the type of object that changed, "push" or "device".
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subtype: Option<String>,
+
+    /// For type="push" messages, the ephemeral that was pushed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub push: Option<Value>,
+}
+
+/// Connection to the realtime event stream. The connection is opened on the first read and reopened by the read after a failed one.
+pub struct Stream {
+    url: String,
+    access_token: String,
+    nop_timeout: Duration,
+    socket: Option<WebSocket<MaybeTlsStream<TcpStream>>>,
+}
+
+impl Stream {
+    pub fn new(access_token: &str) -> Stream {
+        Stream {
+            url: STREAM_URL.to_owned(),
+            access_token: access_token.trim().to_owned(),
+            nop_timeout: Duration::from_secs(90),
+            socket: None,
+        }
+    }
+
+    /// Endpoint to connect to, the access token is appended to it as the last path segment.
+    pub fn url(mut self, url: &str) -> Stream {
+        self.url = url.trim_end_matches('/').to_owned();
+        self
+    }
+
+    /// Time without any message before the connection is considered dead. The server sends a nop every 30 seconds.
+    pub fn nop_timeout(mut self, nop_timeout: Duration) -> Stream {
+        self.nop_timeout = nop_timeout;
+        self
+    }
+
+    /// Block until the next message arrives, connecting first if needed. Messages of unknown types or that fail to parse are skipped.
+    pub fn read(&mut self) -> Result<StreamMessage> {
+        let result = self.read_message();
+        if result.is_err() {
+            self.socket = None;
+        }
+        result
+    }
+
+    fn read_message(&mut self) -> Result<StreamMessage> {
+        let nop_timeout = self.nop_timeout;
+        let socket = match &mut self.socket {
+            Some(socket) => socket,
+            None => self.socket.insert(self.connect()?),
+        };
+
+        loop {
+            let text = match socket.read() {
+                Ok(Message::Text(text)) => text,
+                Ok(Message::Close(frame)) => {
+                    return Err(PbError::Stream(format!("closed by server {frame:?}")));
+                }
+                Ok(_) => continue,
+                Err(tungstenite::Error::Io(e))
+                    if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) =>
+                {
+                    return Err(PbError::Stream(format!(
+                        "no nop received in {}s",
+                        nop_timeout.as_secs()
+                    )));
+                }
+                Err(error) => return Err(PbError::Stream(error.to_string())),
+            };
+
+            match serde_json::from_str::<StreamMessage>(&text) {
+                Ok(message) if matches!(message.t.as_str(), "nop" | "tickle" | "push") => {
+                    return Ok(message);
+                }
+                _ => continue,
+            }
+        }
+    }
+
+    fn connect(&self) -> Result<WebSocket<MaybeTlsStream<TcpStream>>> {
+        let url = format!("{}/{}", self.url, self.access_token);
+        let (socket, _) = tungstenite::connect(url).map_err(|error| match error {
+            tungstenite::Error::Http(response) => PbError::Api {
+                status: response.status().as_u16(),
+                error: None,
+            },
+            // Connection errors carry the url, which ends with the access token.
+            error => PbError::Stream(error.to_string().replace(&self.access_token, "***")),
+        })?;
+        let timeout = Some(self.nop_timeout);
+        let result = match socket.get_ref() {
+            MaybeTlsStream::Plain(tcp) => tcp.set_read_timeout(timeout),
+            MaybeTlsStream::NativeTls(tls) => tls.get_ref().set_read_timeout(timeout),
+            _ => Ok(()),
+        };
+        result.map_err(|e| PbError::Stream(e.to_string()))?;
+        Ok(socket)
+    }
+}
@@ -0,0 +1,106 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::{channel::Channel, Client, Page, Pagination, Paginator, Result};
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct CreateRequest {
+    /// Unique tag for the channel to subscribe to
+    pub channel_tag: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct UpdateRequest {
+    /// true to mute the grant, false to unmute it
+    pub muted: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Subscription {
+    /// Unique identifier for this object
+    pub iden: String,
+
+    /// false if the item has been deleted
+    pub active: bool,
+
+    /// Creation time in floating point seconds (unix timestamp)
+    pub created: f64,
+
+    /// Last modified time in floating point seconds (unix timestamp)
+    pub modified: f64,
+
+    /// If true, notifications from this subscription will not be shown
+    pub muted: Option<bool>,
+
+    /// Information about the channel that is being subscribed to
+    pub channel: Option<Channel>,
+
+    /// Fields not known to this client.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListResponse {
+    pub subscriptions: Vec<Subscription>,
+
+    /// Cursor for the next page of results, absent on the last page.
+    pub cursor: Option<String>,
+
+    /// Fields not known to this client.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl Page for ListResponse {
+    type Item = Subscription;
+
+    fn cursor(&self) -> Option<&str> {
+        self.cursor.as_deref()
+    }
+
+    fn into_items(self) -> Vec<Subscription> {
+        self.subscriptions
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct Subscriptions<'a> {
+    client: &'a Client,
+}
+
+impl<'a> Subscriptions<'a> {
+    pub(crate) fn new(client: &'a Client) -> Subscriptions<'a> {
+        Subscriptions { client }
+    }
+
+    /// Get a list of subscriptions belonging to the current user.
+    pub fn list(&self, pagination: &Pagination) -> Result<ListResponse> {
+        self.client.get("/subscriptions", pagination)
+    }
+
+    /// Iterate over every subscription, following the cursor from page to page.
+    pub fn iter(&self, mut pagination: Pagination) -> Paginator<'a, ListResponse> {
+        let subscriptions = *self;
+        Paginator::new(pagination.cursor.take(), move |cursor| {
+            pagination.cursor = cursor;
+            subscriptions.list(&pagination)
+        })
+    }
+
+    /// Subscribe to a channel.
+    pub fn create(&self, request: &CreateRequest) -> Result<Subscription> {
+        self.client.post("/subscriptions", request)
+    }
+
+    /// Update a subscription.
+    pub fn update(&self, iden: &str, request: &UpdateRequest) -> Result<Subscription> {
+        self.client
+            .post(&format!("/subscriptions/{}", iden), request)
+    }
+
+    /// Unsubscribe from a channel.
+    pub fn delete(&self, iden: &str) -> Result<()> {
+        self.client.delete(&format!("/subscriptions/{}", iden))
+    }
+}
@@ -0,0 +1,124 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::{Client, Result};
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Data {
+    /// The device_iden of the Device to send the message. This device must have SMS Android permissions granted.
+    pub target_device_iden: Option<String>,
+
+    /// A list of 1 more phone numbers to send this message to. Including than one number sends a group MMS message.
+    pub address: Option<String>,
+
+    /// The text content of the text message.
+    pub message: Option<String>,
+
+    /// Unique identifier optionally set by the client, used to identify a text message to ensure it is not sent multiple times in the case create-text is called for it more than once.
+    pub guid: Option<String>,
+
+    /// Unique identifier optionally set by the client, used to identify a text message to ensure it is not sent multiple times in the case create-text is called for it more than once.
+    pub status: Option<String>,
+
+    /// The mime type of the file_url being sent with this message. Only required for messages sending a file.
+    pub file_type: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct CreateRequest {
+    /// Map of values specifying this text message.
+    pub data: Option<Data>,
+
+    /// File download url for an image to send with the text message.
+    pub file_url: Option<String>,
+
+    /// If set to false, delete the attached file when the Text is deleted.
+    pub skip_delete_file: Option<bool>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct UpdateRequest {
+    /// Map of values specifying this text message.
+    pub data: Option<Data>,
+
+    /// When the text is deleted, don't delete the attached file. The file being deleted or not does not affect the MMS that was sent. Can only be set to true
+    pub skip_delete_file: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Text {
+    /// Unique identifier for this object
+    pub iden: String,
+
+    /// false if the item has been deleted
+    pub active: bool,
+
+    /// Creation time in floating point seconds (unix timestamp)
+    pub created: f64,
+
+    /// Last modified time in floating point seconds (unix timestamp)
+    pub modified: f64,
+
+    /// Map of values specifying this text message.
+    pub data: Option<TextData>,
+
+    /// File download url for an image sent with the text message.
+    pub file_url: Option<String>,
+
+    /// When the text is deleted, don't delete the attached file.
+    pub skip_delete_file: Option<bool>,
+
+    /// Fields not known to this client.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TextData {
+    /// The device_iden of the Device sending the message.
+    pub target_device_iden: Option<String>,
+
+    /// A list of 1 more phone numbers the message is sent to.
+    pub addresses: Option<Vec<String>>,
+
+    /// The text content of the text message.
+    pub message: Option<String>,
+
+    /// Unique identifier set by the client.
+    pub guid: Option<String>,
+
+    /// Status of the text message, "queued" until the phone has sent it.
+    pub status: Option<String>,
+
+    /// The mime type of the file_url sent with this message.
+    pub file_type: Option<String>,
+
+    /// Fields not known to this client.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+pub struct Texts<'a> {
+    client: &'a Client,
+}
+
+impl<'a> Texts<'a> {
+    pub(crate) fn new(client: &'a Client) -> Texts<'a> {
+        Texts { client }
+    }
+
+    /// Create a new text. The text will automatically be deleted after an hour whether it has been sent or not.
+    pub fn create(&self, request: &CreateRequest) -> Result<Text> {
+        self.client.post("/texts", request)
+    }
+
+    /// Update a text. If the text has already been sent this will not affect the message.
+    pub fn update(&self, iden: &str, request: &UpdateRequest) -> Result<Text> {
+        self.client.post(&format!("/texts/{}", iden), request)
+    }
+
+    /// Delete a text, canceling it if it has not already been sent.
+    pub fn delete(&self, iden: &str) -> Result<()> {
+        self.client.delete(&format!("/texts/{}", iden))
+    }
+}
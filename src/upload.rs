@@ -0,0 +1,190 @@
+use std::{
+    fs::File,
+    io::{self, Read},
+    path::Path,
+    sync::Arc,
+    thread,
+    time::Duration,
+};
+
+use reqwest::{blocking::multipart, StatusCode};
+use serde::{Deserialize, Serialize};
+
+use crate::{mime, Client, PbError, Result};
+
+/// Largest file Pushbullet accepts, 25 MB.
+pub const MAX_UPLOAD_SIZE: u64 = 25 * 1024 * 1024;
+
+/// Longest wait in seconds between two attempts of an upload.
+const MAX_BACKOFF: u64 = 60;
+
+/// Called with the bytes sent so far and the size of the file while a file is uploaded.
+pub type Progress = Arc<dyn Fn(u64, u64) + Send + Sync>;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct UploadRequestRequest {
+    /// The name of the file you want to upload
+    pub file_name: Option<String>,
+
+    /// The MIME type of the file
+    pub file_type: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UploadRequestResponse {
+    /// The file name that will be used for the file (may be truncated from the original file_name)
+    pub file_name: String,
+
+    /// The file type that will be used for the file (may be different from the one provided to upload-request)
+    pub file_type: String,
+
+    /// The URL where the file will be available after it is uploaded
+    pub file_url: String,
+
+    /// The URL to POST the file to. The file must be posted using multipart/form-data encoding.
+    pub upload_url: String,
+}
+
+/// How upload_file sends a file.
+#[derive(Clone)]
+pub struct UploadOptions {
+    /// MIME type of the file, detected from its content and extension when None.
+    pub file_type: Option<String>,
+
+    /// Number of times a failed upload is tried again, waiting 1, 2, 4... seconds up to a minute in between. The upload url does not support resuming, so every attempt sends the whole file.
+    pub retries: u32,
+
+    pub progress: Option<Progress>,
+}
+
+impl Default for UploadOptions {
+    fn default() -> UploadOptions {
+        UploadOptions {
+            file_type: None,
+            retries: 3,
+            progress: None,
+        }
+    }
+}
+
+/// Reads a file while reporting how much of it was read.
+struct ProgressReader {
+    file: File,
+    sent: u64,
+    size: u64,
+    progress: Option<Progress>,
+}
+
+impl Read for ProgressReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.file.read(buf)?;
+        self.sent += read as u64;
+        if let Some(progress) = &self.progress {
+            progress(self.sent, self.size);
+        }
+        Ok(read)
+    }
+}
+
+impl Client {
+    /// Request an upload url for a file.
+    pub fn upload_request(&self, request: &UploadRequestRequest) -> Result<UploadRequestResponse> {
+        self.post("/upload-request", request)
+    }
+
+    /// Upload a file to the upload url returned by upload-request, streaming it from disk.
+    pub fn upload(&self, path: &str, upload_url: &str, progress: Option<Progress>) -> Result<()> {
+        let status = self.send_file(path, upload_url, progress)?;
+        if !status.is_success() {
+            return Err(PbError::Upload(format!(
+                "{path}: upload url responded with status {status}"
+            )));
+        }
+        Ok(())
+    }
+
+    fn send_file(
+        &self,
+        path: &str,
+        upload_url: &str,
+        progress: Option<Progress>,
+    ) -> Result<StatusCode> {
+        let upload_error = |e: String| PbError::Upload(format!("{path}: {e}"));
+        let file = File::open(path).map_err(|e| upload_error(e.to_string()))?;
+        let size = file
+            .metadata()
+            .map_err(|e| upload_error(e.to_string()))?
+            .len();
+        let reader = ProgressReader {
+            file,
+            sent: 0,
+            size,
+            progress,
+        };
+        let part = multipart::Part::reader_with_length(reader, size)
+            .file_name(file_name(path)?.to_owned());
+        let form = multipart::Form::new().part("file", part);
+
+        // The upload url belongs to the file storage, not to the API, so the access token is not sent.
+        Ok(self.http.post(upload_url).multipart(form).send()?.status())
+    }
+
+    /// Upload a local file, retrying failed uploads. The returned file_name, file_type and file_url can be used to create a type="file" push.
+    pub fn upload_file(
+        &self,
+        path: &str,
+        options: &UploadOptions,
+    ) -> Result<UploadRequestResponse> {
+        let size = Path::new(path)
+            .metadata()
+            .map_err(|e| PbError::Upload(format!("{path}: {e}")))?
+            .len();
+        if size > MAX_UPLOAD_SIZE {
+            return Err(PbError::Upload(format!(
+                "{path}: {:.1} MB is over the {} MB limit of Pushbullet",
+                size as f64 / 1024.0 / 1024.0,
+                MAX_UPLOAD_SIZE / 1024 / 1024
+            )));
+        }
+        let file_type = match &options.file_type {
+            Some(file_type) => file_type.clone(),
+            None => mime::detect(Path::new(path)),
+        };
+
+        let response = self.upload_request(&UploadRequestRequest {
+            file_name: Some(file_name(path)?.to_owned()),
+            file_type: Some(file_type),
+        })?;
+        let mut attempt = 0;
+        loop {
+            let error = match self.send_file(path, &response.upload_url, options.progress.clone()) {
+                Ok(status) if status.is_success() => return Ok(response),
+                Ok(status) => {
+                    let error = PbError::Upload(format!(
+                        "{path}: upload url responded with status {status}"
+                    ));
+                    // Server side statuses may go away, a refused upload will not.
+                    if !status.is_server_error() && status != StatusCode::TOO_MANY_REQUESTS {
+                        return Err(error);
+                    }
+                    error
+                }
+                Err(error @ PbError::Transport(_)) => error,
+                Err(error) => return Err(error),
+            };
+            if attempt >= options.retries {
+                return Err(error);
+            }
+            let backoff = 1u64.checked_shl(attempt).unwrap_or(u64::MAX);
+            thread::sleep(Duration::from_secs(backoff.min(MAX_BACKOFF)));
+            attempt += 1;
+        }
+    }
+}
+
+fn file_name(path: &str) -> Result<&str> {
+    Path::new(path)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| PbError::Upload(format!("invalid file name {path}")))
+}
@@ -0,0 +1,56 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::{Client, Result};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct User {
+    /// Unique identifier for the current user
+    pub iden: String,
+
+    /// Creation time in floating point seconds (unix timestamp)
+    pub created: f64,
+
+    /// Last modified time in floating point seconds (unix timestamp)
+    pub modified: f64,
+
+    /// Email address
+    pub email: Option<String>,
+
+    /// Canonical email address
+    pub email_normalized: Option<String>,
+
+    /// Full name if available
+    pub name: Option<String>,
+
+    /// URL for image of user or placeholder image
+    pub image_url: Option<String>,
+
+    /// Maximum upload size in bytes
+    pub max_upload_size: Option<f64>,
+
+    /// Number of users referred by this user
+    pub referred_count: Option<i64>,
+
+    /// User iden for the user that referred the current user, if set
+    pub referrer_iden: Option<String>,
+
+    /// Fields not known to this client.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+pub struct Users<'a> {
+    client: &'a Client,
+}
+
+impl<'a> Users<'a> {
+    pub(crate) fn new(client: &'a Client) -> Users<'a> {
+        Users { client }
+    }
+
+    /// Gets the currently logged in user.
+    pub fn me(&self) -> Result<User> {
+        self.client.get("/users/me", &())
+    }
+}
@@ -0,0 +1,286 @@
+mod common;
+
+use std::{
+    io::Write,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use common::{push, StreamStub, Stub};
+use pushbullet_rust::{
+    ephemeral::{EncryptionKey, EphemeralPush},
+    mime,
+    push::{CreateRequest, ListRequest},
+    Client, PbError, UploadOptions, MAX_UPLOAD_SIZE,
+};
+use serde_json::json;
+use tempfile::NamedTempFile;
+
+#[test]
+fn list_pushes_sends_token_and_query() {
+    let stub = Stub::start(|_| {
+        let body = json!({ "pushes": [push("a", "first")], "cursor": "next" });
+        (200, body.to_string())
+    });
+    let client = Client::new("token").with_api_url(&stub.url);
+
+    let response = client
+        .pushes()
+        .list(&ListRequest {
+            active: Some(true),
+            limit: Some(10),
+            ..Default::default()
+        })
+        .unwrap();
+
+    assert_eq!(response.pushes[0].iden, "a");
+    assert_eq!(response.cursor.as_deref(), Some("next"));
+    let request = &stub.requests()[0];
+    assert_eq!(request.method, "GET");
+    assert_eq!(request.path, "/v2/pushes");
+    assert_eq!(request.header("Access-Token"), Some("token"));
+    assert_eq!(request.query_param("active").as_deref(), Some("true"));
+    assert_eq!(request.query_param("limit").as_deref(), Some("10"));
+    assert_eq!(request.query_param("cursor"), None);
+}
+
+#[test]
+fn create_push_posts_json_body() {
+    let stub = Stub::start(|_| (200, push("p", "hello").to_string()));
+    let client = Client::new("token").with_api_url(&stub.url);
+
+    let created = client
+        .pushes()
+        .create(&CreateRequest {
+            t: Some(String::from("note")),
+            title: Some(String::from("hello")),
+            ..Default::default()
+        })
+        .unwrap();
+
+    assert_eq!(created.iden, "p");
+    let request = &stub.requests()[0];
+    assert_eq!(request.method, "POST");
+    assert_eq!(request.path, "/v2/pushes");
+    assert_eq!(request.json()["type"], "note");
+    assert_eq!(request.json()["title"], "hello");
+}
+
+#[test]
+fn error_status_is_reported_with_body() {
+    let stub = Stub::start(|_| {
+        let body = json!({ "error": {
+            "code": "invalid_access_token",
+            "type": "invalid_request",
+            "message": "Access token is missing or invalid.",
+            "cite": "https://docs.pushbullet.com"
+        }});
+        (401, body.to_string())
+    });
+    let client = Client::new("bad").with_api_url(&stub.url);
+
+    match client.users().me() {
+        Err(PbError::Api { status, error }) => {
+            assert_eq!(status, 401);
+            assert_eq!(error.unwrap().code.as_deref(), Some("invalid_access_token"));
+        }
+        other => panic!("unexpected result {other:?}"),
+    }
+}
+
+#[test]
+fn iter_follows_cursor() {
+    let stub = Stub::start(|request| {
+        let body = match request.query_param("cursor").as_deref() {
+            None => json!({ "pushes": [push("a", "1"), push("b", "2")], "cursor": "page2" }),
+            Some("page2") => json!({ "pushes": [push("c", "3")] }),
+            Some(_) => json!({ "pushes": [] }),
+        };
+        (200, body.to_string())
+    });
+    let client = Client::new("token").with_api_url(&stub.url);
+
+    let idens: Vec<String> = client
+        .pushes()
+        .iter(ListRequest::default())
+        .map(|push| push.unwrap().iden)
+        .collect();
+
+    assert_eq!(idens, ["a", "b", "c"]);
+    assert_eq!(stub.requests().len(), 2);
+}
+
+#[test]
+fn unknown_fields_are_kept() {
+    let stub = Stub::start(|_| {
+        let mut device = json!({ "iden": "d", "active": true, "created": 1.0, "modified": 2.0 });
+        device["new_field"] = json!("value");
+        (200, json!({ "devices": [device] }).to_string())
+    });
+    let client = Client::new("token").with_api_url(&stub.url);
+
+    let response = client.devices().list(&Default::default()).unwrap();
+
+    assert_eq!(response.devices[0].extra["new_field"], "value");
+}
+
+fn upload_stub(upload_statuses: Vec<u16>) -> Stub {
+    let attempts = AtomicUsize::new(0);
+    Stub::start(move |request| match request.path.as_str() {
+        "/v2/upload-request" => {
+            let body = json!({
+                "file_name": "data.bin",
+                "file_type": "application/octet-stream",
+                "file_url": "https://files.example/data.bin",
+                "upload_url": format!("http://{}/upload", request.header("Host").unwrap()),
+            });
+            (200, body.to_string())
+        }
+        _ => {
+            let attempt = attempts.fetch_add(1, Ordering::SeqCst);
+            let status = upload_statuses.get(attempt).copied().unwrap_or(204);
+            (status, String::new())
+        }
+    })
+}
+
+#[test]
+fn upload_over_limit_fails_before_any_request() {
+    let stub = upload_stub(vec![]);
+    let client = Client::new("token").with_api_url(&stub.url);
+    let file = NamedTempFile::new().unwrap();
+    file.as_file().set_len(MAX_UPLOAD_SIZE + 1).unwrap();
+
+    let result = client.upload_file(file.path().to_str().unwrap(), &UploadOptions::default());
+
+    assert!(matches!(result, Err(PbError::Upload(message)) if message.contains("25 MB")));
+    assert!(stub.requests().is_empty());
+}
+
+#[test]
+fn upload_is_retried_and_reports_progress() {
+    let stub = upload_stub(vec![503]);
+    let client = Client::new("token").with_api_url(&stub.url);
+    let mut file = NamedTempFile::new().unwrap();
+    file.write_all(b"payload").unwrap();
+    let sent = Arc::new(AtomicUsize::new(0));
+    let progress = sent.clone();
+
+    let response = client
+        .upload_file(
+            file.path().to_str().unwrap(),
+            &UploadOptions {
+                file_type: Some(String::from("application/octet-stream")),
+                progress: Some(Arc::new(move |sent, _| {
+                    progress.store(sent as usize, Ordering::SeqCst)
+                })),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+    assert_eq!(response.file_url, "https://files.example/data.bin");
+    let requests = stub.requests();
+    assert_eq!(requests.len(), 3);
+    assert_eq!(requests[2].path, "/upload");
+    assert!(String::from_utf8_lossy(&requests[2].body).contains("payload"));
+    assert_eq!(requests[2].header("Access-Token"), None);
+    assert_eq!(sent.load(Ordering::SeqCst), 7);
+}
+
+#[test]
+fn refused_upload_is_not_retried() {
+    let stub = upload_stub(vec![403]);
+    let client = Client::new("token").with_api_url(&stub.url);
+    let file = NamedTempFile::new().unwrap();
+
+    let result = client.upload_file(
+        file.path().to_str().unwrap(),
+        &UploadOptions {
+            file_type: Some(String::from("text/plain")),
+            ..Default::default()
+        },
+    );
+
+    assert!(matches!(result, Err(PbError::Upload(message)) if message.contains("403")));
+    assert_eq!(stub.requests().len(), 2);
+}
+
+#[test]
+fn sniff_mime_types_from_content_and_extension() {
+    assert_eq!(
+        mime::sniff(b"\x89PNG\r\n\x1a\n....", Some("txt")),
+        "image/png"
+    );
+    assert_eq!(
+        mime::sniff(b"PK\x03\x04....", Some("docx")),
+        "application/vnd.openxmlformats-officedocument.wordprocessingml.document"
+    );
+    assert_eq!(mime::sniff(b"PK\x03\x04....", None), "application/zip");
+    assert_eq!(mime::sniff(b"RIFF....WEBPVP8 ", None), "image/webp");
+    assert_eq!(mime::sniff(b"a,b\n1,2\n", Some("csv")), "text/csv");
+    assert_eq!(mime::sniff("caf\u{e9}\n".as_bytes(), None), "text/plain");
+    assert_eq!(mime::sniff(b"\0\x01\x02\x03", None), mime::DEFAULT_TYPE);
+}
+
+#[test]
+fn ephemerals_are_encrypted_end_to_end() {
+    let stub = Stub::start(|_| (200, String::from("{}")));
+    let client = Client::new("token").with_api_url(&stub.url);
+    let key = EncryptionKey::derive("hunter2", "ujpah72o0");
+    // Encrypted with the same password and user iden by another AES-GCM implementation.
+    let message =
+        "MatVEgEb+VhnHW9ViPABpYIAAQIDBAUGBwgJCgvA9DBz+sjOhGzOV9SJ9RZvohUmLiFKIdUnrjY5jjXc";
+    assert_eq!(
+        key.decrypt(message).unwrap(),
+        br#"{"type":"clip","body":"copied"}"#
+    );
+
+    let clip = EphemeralPush::Clip {
+        body: String::from("copied"),
+        source_user_iden: String::from("ujpah72o0"),
+        source_device_iden: None,
+    };
+    client.ephemerals().send(&clip, Some(&key)).unwrap();
+
+    let request = &stub.requests()[0];
+    assert_eq!(request.path, "/v2/ephemerals");
+    let body = request.json();
+    assert_eq!(body["type"], "push");
+    assert_eq!(body["push"]["encrypted"], true);
+    assert_eq!(
+        key.decrypt_push(&body["push"]).unwrap(),
+        json!({ "type": "clip", "body": "copied", "source_user_iden": "ujpah72o0" })
+    );
+    let other_key = EncryptionKey::derive("hunter3", "ujpah72o0");
+    assert!(matches!(
+        other_key.decrypt_push(&body["push"]),
+        Err(PbError::Config(_))
+    ));
+}
+
+#[test]
+fn stream_reads_messages_and_reconnects() {
+    let stub = StreamStub::start(vec![
+        json!({ "type": "nop" }),
+        json!({ "type": "tickle", "subtype": "push" }),
+        json!({ "type": "unknown" }),
+        json!({ "type": "push", "push": { "type": "mirror", "title": "hi" } }),
+    ]);
+    let mut stream = Client::new("token").stream().url(&stub.url);
+
+    assert_eq!(stream.read().unwrap().t, "nop");
+    let tickle = stream.read().unwrap();
+    assert_eq!(tickle.t, "tickle");
+    assert_eq!(tickle.subtype.as_deref(), Some("push"));
+    let push = stream.read().unwrap();
+    assert_eq!(push.t, "push");
+    assert_eq!(push.push.unwrap()["title"], "hi");
+    assert!(matches!(stream.read(), Err(PbError::Stream(_))));
+    // The read after a failed one connects again.
+    assert_eq!(stream.read().unwrap().t, "nop");
+
+    assert_eq!(stub.paths(), ["/websocket/token", "/websocket/token"]);
+}
@@ -0,0 +1,2036 @@
+mod common;
+
+use std::{
+    fs,
+    io::Write,
+    path::Path,
+    process::{Command, Output, Stdio},
+};
+
+use common::{push, Request, StreamStub, Stub};
+use serde_json::{json, Value};
+use tempfile::TempDir;
+
+fn pb(home: &Path) -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_pb"));
+    command
+        .env("HOME", home)
+        .env_remove("PB_API_URL")
+        .env_remove("PB_PROFILE")
+        .env_remove("PB_ACCESS_TOKEN")
+        .env_remove("PB_KEYRING_PASSPHRASE")
+        .env_remove("PB_ENCRYPTION_PASSWORD")
+        .env_remove("PB_STREAM_URL")
+        .env_remove("XDG_RUNTIME_DIR");
+    command
+}
+
+fn home_with_token(token: &str) -> TempDir {
+    let home = TempDir::new().unwrap();
+    let output = pb(home.path())
+        .args(["access-token", token])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    home
+}
+
+fn stdout_json(output: &Output) -> Value {
+    assert!(
+        output.status.success(),
+        "pb failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    serde_json::from_slice(&output.stdout).unwrap()
+}
+
+#[test]
+fn api_url_flag() {
+    let stub = Stub::start(|_| (200, json!({ "pushes": [push("a", "hi")] }).to_string()));
+    let home = home_with_token("token");
+
+    let output = pb(home.path())
+        .args(["--api-url", &stub.url, "push", "list"])
+        .output()
+        .unwrap();
+
+    assert_eq!(stdout_json(&output)["pushes"][0]["title"], "hi");
+    let request = &stub.requests()[0];
+    assert_eq!(request.path, "/v2/pushes");
+    assert_eq!(request.header("Access-Token"), Some("token"));
+}
+
+#[test]
+fn api_url_environment_variable() {
+    let stub = Stub::start(|_| {
+        (
+            200,
+            json!({ "iden": "u", "created": 1.0, "modified": 1.0 }).to_string(),
+        )
+    });
+    let home = home_with_token("token");
+
+    let output = pb(home.path())
+        .env("PB_API_URL", &stub.url)
+        .args(["user", "get"])
+        .output()
+        .unwrap();
+
+    assert_eq!(stdout_json(&output)["iden"], "u");
+    assert_eq!(stub.requests()[0].path, "/v2/users/me");
+}
+
+#[test]
+fn api_url_stored_in_config() {
+    let stub = Stub::start(|_| (200, json!({ "devices": [] }).to_string()));
+    let home = home_with_token("token");
+
+    let output = pb(home.path())
+        .args(["api-url", &format!("{}/", stub.url)])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let output = pb(home.path()).args(["device", "list"]).output().unwrap();
+
+    assert_eq!(stdout_json(&output)["devices"], json!([]));
+    assert_eq!(stub.requests()[0].path, "/v2/devices");
+    let config = fs::read_to_string(home.path().join(".config/pbr/config")).unwrap();
+    assert!(config.contains("access_token = \"token\""));
+}
+
+#[test]
+fn bare_token_file_is_still_read() {
+    let stub = Stub::start(|_| (200, json!({ "chats": [] }).to_string()));
+    let home = TempDir::new().unwrap();
+    fs::create_dir_all(home.path().join(".config/pbr")).unwrap();
+    fs::write(home.path().join(".config/pbr/config"), "o.legacy\n").unwrap();
+
+    let output = pb(home.path())
+        .args(["--api-url", &stub.url, "chat", "list"])
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(stub.requests()[0].header("Access-Token"), Some("o.legacy"));
+}
+
+#[test]
+fn error_status_sets_exit_code() {
+    let stub = Stub::start(|_| {
+        let error = json!({ "error": { "type": "invalid_request", "message": "Access token is missing or invalid." } });
+        (401, error.to_string())
+    });
+    let home = home_with_token("bad");
+
+    let output = pb(home.path())
+        .args(["--api-url", &stub.url, "push", "list"])
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.code(), Some(5));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Access token is missing or invalid."));
+}
+
+#[test]
+fn list_all_merges_pages() {
+    let stub = Stub::start(|request| {
+        let body = match request.query_param("cursor").as_deref() {
+            None => json!({ "pushes": [push("a", "1"), push("b", "2")], "cursor": "next" }),
+            _ => json!({ "pushes": [push("c", "3")], "cursor": "last" }),
+        };
+        (200, body.to_string())
+    });
+    let home = home_with_token("token");
+
+    let output = pb(home.path())
+        .args([
+            "--api-url",
+            &stub.url,
+            "push",
+            "list",
+            "--all",
+            "--max-items",
+            "3",
+        ])
+        .output()
+        .unwrap();
+
+    let pushes = stdout_json(&output)["pushes"].clone();
+    assert_eq!(pushes.as_array().unwrap().len(), 3);
+    assert_eq!(stub.requests().len(), 2);
+}
+
+#[test]
+fn file_push_uploads_before_creating() {
+    let stub = Stub::start(|request| match request.path.as_str() {
+        "/v2/upload-request" => {
+            let upload_url = format!("http://{}/upload", request.header("Host").unwrap());
+            let body = json!({
+                "file_name": "notes.txt",
+                "file_type": "text/plain",
+                "file_url": "https://files.example/notes.txt",
+                "upload_url": upload_url,
+            });
+            (200, body.to_string())
+        }
+        "/upload" => (204, String::new()),
+        _ => {
+            let mut push = push("f", "file");
+            push["file_url"] = request.json()["file_url"].clone();
+            (200, push.to_string())
+        }
+    });
+    let home = home_with_token("token");
+    let file = home.path().join("notes.txt");
+    fs::write(&file, "some notes").unwrap();
+
+    let output = pb(home.path())
+        .args(["--api-url", &stub.url, "push", "create", "--type", "file"])
+        .args(["--file-type", "text/plain", "--file-name"])
+        .arg(&file)
+        .output()
+        .unwrap();
+
+    assert_eq!(
+        stdout_json(&output)["file_url"],
+        "https://files.example/notes.txt"
+    );
+    let paths: Vec<String> = stub.requests().into_iter().map(|r| r.path).collect();
+    assert_eq!(paths, ["/v2/upload-request", "/upload", "/v2/pushes"]);
+    let upload = &stub.requests()[1];
+    assert!(String::from_utf8_lossy(&upload.body).contains("some notes"));
+}
+
+#[test]
+fn profiles_select_access_token() {
+    let stub = Stub::start(|_| (200, json!({ "pushes": [] }).to_string()));
+    let home = home_with_token("personal");
+    let output = pb(home.path())
+        .args(["--profile", "team", "access-token", "team-token"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    pb(home.path())
+        .args(["--api-url", &stub.url, "push", "list"])
+        .output()
+        .unwrap();
+    let output = pb(home.path())
+        .args(["config", "use-profile", "team"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    pb(home.path())
+        .args(["--api-url", &stub.url, "push", "list"])
+        .output()
+        .unwrap();
+    pb(home.path())
+        .env("PB_PROFILE", "default")
+        .args(["--api-url", &stub.url, "push", "list"])
+        .output()
+        .unwrap();
+
+    let tokens: Vec<String> = stub
+        .requests()
+        .iter()
+        .map(|request| request.header("Access-Token").unwrap().to_owned())
+        .collect();
+    assert_eq!(tokens, ["personal", "team-token", "personal"]);
+}
+
+#[test]
+fn config_set_and_get() {
+    let home = home_with_token("token");
+
+    let output = pb(home.path())
+        .args(["config", "set", "timeout", "15"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let output = pb(home.path())
+        .args(["config", "get", "timeout"])
+        .output()
+        .unwrap();
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "15\n");
+
+    let output = pb(home.path())
+        .args(["config", "set", "timeout", "soon"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(3));
+    let output = pb(home.path())
+        .args(["config", "get", "device"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(3));
+}
+
+#[test]
+fn flat_config_moves_to_default_profile() {
+    let stub = Stub::start(|_| (200, json!({ "devices": [] }).to_string()));
+    let home = TempDir::new().unwrap();
+    fs::create_dir_all(home.path().join(".config/pbr")).unwrap();
+    let config = format!("access_token = \"flat\"\napi_url = \"{}\"\n", stub.url);
+    fs::write(home.path().join(".config/pbr/config"), config).unwrap();
+
+    let output = pb(home.path()).args(["device", "list"]).output().unwrap();
+
+    assert_eq!(stdout_json(&output)["devices"], json!([]));
+    assert_eq!(stub.requests()[0].header("Access-Token"), Some("flat"));
+}
+
+#[test]
+fn invalid_config_is_repaired_by_access_token() {
+    let home = TempDir::new().unwrap();
+    let dir = home.path().join(".config/pbr");
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("config"), "[profiles\nbroken = \n").unwrap();
+
+    let output = pb(home.path()).args(["device", "list"]).output().unwrap();
+    assert_eq!(output.status.code(), Some(3));
+
+    let output = pb(home.path())
+        .args(["access-token", "tok2"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let output = pb(home.path())
+        .args(["config", "get", "access_token"])
+        .output()
+        .unwrap();
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "tok2\n");
+    assert_eq!(
+        fs::read_to_string(dir.join("config.bak")).unwrap(),
+        "[profiles\nbroken = \n"
+    );
+}
+
+#[test]
+fn default_device_is_push_target() {
+    let stub = Stub::start(|request| match names(request) {
+        Some(response) => response,
+        None => (200, push("p", "hi").to_string()),
+    });
+    let home = home_with_token("token");
+    pb(home.path())
+        .args(["config", "set", "device", "phone"])
+        .output()
+        .unwrap();
+
+    pb(home.path())
+        .args(["--api-url", &stub.url, "push", "create", "--title", "hi"])
+        .output()
+        .unwrap();
+    pb(home.path())
+        .args(["--api-url", &stub.url, "push", "create", "--email", "a@b.c"])
+        .output()
+        .unwrap();
+
+    let requests: Vec<_> = stub
+        .requests()
+        .into_iter()
+        .filter(|request| request.method == "POST")
+        .collect();
+    assert_eq!(requests[0].json()["device_iden"], "ujphone");
+    assert_eq!(requests[1].json()["device_iden"], Value::Null);
+}
+
+/// Devices, chats and channels that names resolve to, for stubs of commands taking a push target.
+fn names(request: &Request) -> Option<(u16, String)> {
+    let body = match request.path.as_str() {
+        "/v2/devices" => json!({ "devices": [
+            { "iden": "ujphone", "active": true, "created": 1.0, "modified": 1.0, "nickname": "Phone" },
+            { "iden": "ujpixel7", "active": true, "created": 1.0, "modified": 1.0, "nickname": "Pixel 7" },
+            { "iden": "ujpixel8", "active": true, "created": 1.0, "modified": 1.0, "nickname": "Pixel 8" },
+        ]}),
+        "/v2/chats" => json!({ "chats": [
+            { "iden": "c1", "active": true, "created": 1.0, "modified": 1.0, "with": { "email": "ana@example.com" } },
+        ]}),
+        "/v2/subscriptions" => json!({ "subscriptions": [
+            { "iden": "s1", "active": true, "created": 1.0, "modified": 1.0, "channel": { "iden": "ch1", "tag": "releases" } },
+        ]}),
+        _ => return None,
+    };
+    Some((200, body.to_string()))
+}
+
+#[test]
+fn access_token_sources_in_order() {
+    let stub = Stub::start(|_| (200, json!({ "chats": [] }).to_string()));
+    let home = home_with_token("file");
+    let list = |command: &mut Command| {
+        let output = command
+            .args(["--api-url", &stub.url, "chat", "list"])
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+    };
+
+    list(&mut pb(home.path()));
+    pb(home.path())
+        .args([
+            "config",
+            "set",
+            "token_command",
+            "echo command; echo second",
+        ])
+        .output()
+        .unwrap();
+    list(&mut pb(home.path()));
+    list(pb(home.path()).args(["--token", "flag"]));
+    list(
+        pb(home.path())
+            .env("PB_ACCESS_TOKEN", "environment")
+            .args(["--token", "flag"]),
+    );
+
+    let tokens: Vec<String> = stub
+        .requests()
+        .iter()
+        .map(|request| request.header("Access-Token").unwrap().to_owned())
+        .collect();
+    assert_eq!(tokens, ["file", "command", "flag", "environment"]);
+}
+
+#[test]
+fn failing_token_command_is_config_error() {
+    let home = home_with_token("file");
+    pb(home.path())
+        .args(["config", "set", "token_command", "exit 1"])
+        .output()
+        .unwrap();
+
+    let output = pb(home.path()).args(["user", "get"]).output().unwrap();
+
+    assert_eq!(output.status.code(), Some(3));
+}
+
+#[test]
+fn access_token_from_stdin() {
+    let home = TempDir::new().unwrap();
+    let mut child = pb(home.path())
+        .args(["access-token", "--stdin"])
+        .stdin(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(b"o.piped\n").unwrap();
+    assert!(child.wait().unwrap().success());
+
+    let output = pb(home.path())
+        .args(["config", "get", "access_token"])
+        .output()
+        .unwrap();
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "o.piped\n");
+}
+
+#[test]
+fn keyring_token_is_encrypted() {
+    let stub = Stub::start(|_| (200, json!({ "chats": [] }).to_string()));
+    let home = TempDir::new().unwrap();
+    let runtime_dir = TempDir::new().unwrap();
+    let output = pb(home.path())
+        .env("PB_KEYRING_PASSPHRASE", "secret")
+        .args(["access-token", "--keyring", "o.encrypted"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let stored = fs::read_to_string(home.path().join(".config/pbr/default.age")).unwrap();
+    assert!(stored.starts_with("-----BEGIN AGE ENCRYPTED FILE-----"));
+    assert!(!stored.contains("o.encrypted"));
+    let config = fs::read_to_string(home.path().join(".config/pbr/config")).unwrap();
+    assert!(!config.contains("o.encrypted"));
+
+    let output = pb(home.path())
+        .env("PB_KEYRING_PASSPHRASE", "wrong")
+        .args(["--api-url", &stub.url, "chat", "list"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(3));
+
+    let output = pb(home.path())
+        .env("PB_KEYRING_PASSPHRASE", "secret")
+        .env("XDG_RUNTIME_DIR", runtime_dir.path())
+        .args(["--api-url", &stub.url, "chat", "list"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    // Remembered, so no passphrase is needed the second time.
+    let output = pb(home.path())
+        .env("XDG_RUNTIME_DIR", runtime_dir.path())
+        .args(["--api-url", &stub.url, "chat", "list"])
+        .stdin(Stdio::null())
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let tokens: Vec<String> = stub
+        .requests()
+        .iter()
+        .map(|request| request.header("Access-Token").unwrap().to_owned())
+        .collect();
+    assert_eq!(tokens, ["o.encrypted", "o.encrypted"]);
+}
+
+#[cfg(unix)]
+#[test]
+fn profile_names_cannot_leave_the_config_directory() {
+    let home = TempDir::new().unwrap();
+
+    let output = pb(home.path())
+        .args(["--profile", "../../x", "access-token", "--keyring", "tok"])
+        .env("PB_KEYRING_PASSPHRASE", "secret")
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.code(), Some(3));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("invalid profile name"));
+    assert!(!home.path().join("x.age").exists());
+    assert!(!home.path().join(".config/pbr/config").exists());
+}
+
+#[test]
+fn config_is_private() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let home = TempDir::new().unwrap();
+    let path = home.path().join(".config/pbr/config");
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(&path, "access_token = \"a much longer old token\"\n").unwrap();
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+
+    let output = pb(home.path())
+        .args(["access-token", "short"])
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    let mode = fs::metadata(&path).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o600);
+    let config = fs::read_to_string(&path).unwrap();
+    assert!(!config.contains("old token"));
+}
+
+fn devices_stub() -> Stub {
+    Stub::start(|_| {
+        let devices = json!({ "devices": [
+            { "iden": "d1", "active": true, "created": 1.0, "modified": 1.0, "nickname": "Phone", "model": "Pixel" },
+            { "iden": "d2", "active": true, "created": 1.0, "modified": 1.0, "nickname": "Laptop\nwork" },
+        ], "cursor": "more" });
+        (200, devices.to_string())
+    })
+}
+
+fn list_devices(home: &Path, stub: &Stub, args: &[&str]) -> Output {
+    pb(home)
+        .args(["--api-url", &stub.url, "device", "list"])
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn table_output() {
+    let stub = devices_stub();
+    let home = home_with_token("token");
+
+    let output = list_devices(
+        home.path(),
+        &stub,
+        &["--output", "table", "--columns", "iden,nickname,model"],
+    );
+
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "IDEN  NICKNAME     MODEL\nd1    Phone        Pixel\nd2    Laptop work\n"
+    );
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--cursor more"));
+}
+
+#[test]
+fn jsonl_output_with_columns() {
+    let stub = devices_stub();
+    let home = home_with_token("token");
+
+    let output = list_devices(
+        home.path(),
+        &stub,
+        &["--output", "jsonl", "--columns", "iden"],
+    );
+
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "{\"iden\":\"d1\"}\n{\"iden\":\"d2\"}\n"
+    );
+}
+
+#[test]
+fn json_output_with_columns_keeps_cursor() {
+    let stub = devices_stub();
+    let home = home_with_token("token");
+
+    let output = list_devices(home.path(), &stub, &["--columns", "iden,model"]);
+
+    assert_eq!(
+        stdout_json(&output),
+        json!({ "devices": [{ "iden": "d1", "model": "Pixel" }, { "iden": "d2" }], "cursor": "more" })
+    );
+}
+
+#[test]
+fn template_output() {
+    let stub = devices_stub();
+    let home = home_with_token("token");
+
+    let output = list_devices(home.path(), &stub, &["--template", "{{iden}}={{ model }}"]);
+
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "d1=Pixel\nd2=\n");
+}
+
+#[test]
+fn output_from_profile() {
+    let stub = devices_stub();
+    let home = home_with_token("token");
+    pb(home.path())
+        .args(["config", "set", "output", "jsonl"])
+        .output()
+        .unwrap();
+
+    let output = list_devices(home.path(), &stub, &[]);
+    assert_eq!(String::from_utf8_lossy(&output.stdout).lines().count(), 2);
+    let output = list_devices(home.path(), &stub, &["--output", "json"]);
+    assert_eq!(String::from_utf8_lossy(&output.stdout).lines().count(), 1);
+}
+
+fn send(home: &Path, stub: &Stub, args: &[&str]) -> Value {
+    let output = pb(home)
+        .args(["--api-url", &stub.url, "send"])
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "pb failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    stub.requests().last().unwrap().json()
+}
+
+#[test]
+fn send_detects_link_and_note() {
+    let stub = Stub::start(|_| (200, push("p", "sent").to_string()));
+    let home = home_with_token("token");
+
+    let link = send(home.path(), &stub, &["https://example.com/page?q=1"]);
+    assert_eq!(link["type"], "link");
+    assert_eq!(link["url"], "https://example.com/page?q=1");
+    assert_eq!(link["title"], "example.com");
+
+    let note = send(home.path(), &stub, &["Groceries\nmilk\neggs"]);
+    assert_eq!(note["type"], "note");
+    assert_eq!(note["title"], "Groceries");
+    assert_eq!(note["body"], "milk\neggs");
+
+    let note = send(home.path(), &stub, &["call back", "--title", "Reminder"]);
+    assert_eq!(note["title"], "Reminder");
+    assert_eq!(note["body"], "call back");
+
+    let forced = send(
+        home.path(),
+        &stub,
+        &["https://example.com", "--type", "note"],
+    );
+    assert_eq!(forced["type"], "note");
+}
+
+#[test]
+fn send_uploads_existing_file_to_default_device() {
+    let stub = Stub::start(|request| match request.path.as_str() {
+        "/v2/upload-request" => {
+            let body = json!({
+                "file_name": "report.txt",
+                "file_type": "text/plain",
+                "file_url": "https://files.example/report.txt",
+                "upload_url": format!("http://{}/upload", request.header("Host").unwrap()),
+            });
+            (200, body.to_string())
+        }
+        "/upload" => (204, String::new()),
+        "/v2/pushes" => (200, push("f", "file").to_string()),
+        _ => names(request).unwrap(),
+    });
+    let home = home_with_token("token");
+    pb(home.path())
+        .args(["config", "set", "device", "phone"])
+        .output()
+        .unwrap();
+    let file = home.path().join("report.txt");
+    fs::write(&file, "numbers").unwrap();
+
+    let created = send(home.path(), &stub, &[file.to_str().unwrap()]);
+
+    assert_eq!(created["type"], "file");
+    assert_eq!(created["file_url"], "https://files.example/report.txt");
+    assert_eq!(created["device_iden"], "ujphone");
+    let upload_request = stub
+        .requests()
+        .into_iter()
+        .find(|request| request.path == "/v2/upload-request")
+        .unwrap();
+    assert_eq!(upload_request.json()["file_name"], "report.txt");
+}
+
+fn pipe(home: &Path, stub: &Stub, args: &[&str], input: &str) -> Output {
+    let mut child = pb(home)
+        .args(["--api-url", &stub.url])
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+#[test]
+fn push_body_from_stdin() {
+    let stub = Stub::start(|_| (200, push("p", "log").to_string()));
+    let home = home_with_token("token");
+
+    let output = pipe(
+        home.path(),
+        &stub,
+        &["push", "create", "--type", "note", "--body", "-"],
+        "line one\nline two\n",
+    );
+
+    assert!(output.status.success());
+    assert_eq!(stub.requests()[0].json()["body"], "line one\nline two\n");
+}
+
+#[test]
+fn pipe_splits_input_into_chunks() {
+    let stub = Stub::start(|_| (200, push("p", "chunk").to_string()));
+    let home = home_with_token("token");
+    let input: String = (1..=30).map(|i| format!("line {i:02}\n")).collect();
+
+    let output = pipe(
+        home.path(),
+        &stub,
+        &["pipe", "--title", "build", "--chunk-size", "100"],
+        &input,
+    );
+
+    assert_eq!(stdout_json(&output)["pushes"].as_array().unwrap().len(), 3);
+    let bodies: Vec<Value> = stub.requests().iter().map(|r| r.json()).collect();
+    assert_eq!(bodies[0]["title"], "build (1/3)");
+    assert_eq!(bodies[2]["title"], "build (3/3)");
+    let joined: Vec<String> = bodies
+        .iter()
+        .map(|body| body["body"].as_str().unwrap().to_owned())
+        .collect();
+    assert_eq!(joined.join("\n") + "\n", input);
+    assert!(bodies
+        .iter()
+        .all(|body| body["body"].as_str().unwrap().len() <= 100));
+}
+
+#[test]
+fn pipe_names_chunks_sent_before_a_failure() {
+    let stub = Stub::start(|request| match request.json()["body"].as_str() {
+        Some("second") => (500, json!({ "error": { "message": "down" } }).to_string()),
+        _ => (200, push("p1", "chunk").to_string()),
+    });
+    let home = home_with_token("token");
+
+    let output = pipe(
+        home.path(),
+        &stub,
+        &["pipe", "--chunk-size", "6"],
+        "first\nsecond\nthird\n",
+    );
+
+    assert_eq!(output.status.code(), Some(8));
+    assert!(
+        String::from_utf8_lossy(&output.stderr).contains("Sent 1 of 3 chunks before failing: p1")
+    );
+    assert_eq!(stub.requests().len(), 2);
+}
+
+#[test]
+fn pipe_tail_sends_last_lines() {
+    let stub = Stub::start(|_| (200, push("p", "tail").to_string()));
+    let home = home_with_token("token");
+
+    let output = pipe(
+        home.path(),
+        &stub,
+        &["pipe", "--title", "nightly build", "--tail", "2"],
+        "compiling\ntesting\nok\ndone\n",
+    );
+
+    assert!(output.status.success());
+    let body = stub.requests()[0].json();
+    assert_eq!(body["title"], "nightly build");
+    assert_eq!(body["body"], "ok\ndone");
+}
+
+#[test]
+fn pipe_empty_input_sends_nothing() {
+    let stub = Stub::start(|_| (200, push("p", "none").to_string()));
+    let home = home_with_token("token");
+
+    let output = pipe(home.path(), &stub, &["pipe"], "");
+
+    assert_eq!(stdout_json(&output), json!({ "pushes": [] }));
+    assert!(stub.requests().is_empty());
+}
+
+#[test]
+fn pipe_sends_no_empty_chunks() {
+    let stub = Stub::start(|_| (200, push("p", "chunk").to_string()));
+    let home = home_with_token("token");
+
+    let output = pipe(home.path(), &stub, &["pipe", "--tail", "3"], "");
+    assert_eq!(stdout_json(&output), json!({ "pushes": [] }));
+    let output = pipe(
+        home.path(),
+        &stub,
+        &["pipe", "--chunk-size", "4"],
+        "aaaa\n\n\nbbbb\n",
+    );
+
+    assert_eq!(stdout_json(&output)["pushes"].as_array().unwrap().len(), 2);
+    let bodies: Vec<Value> = stub
+        .requests()
+        .iter()
+        .map(|request| request.json()["body"].clone())
+        .collect();
+    assert_eq!(bodies, ["aaaa", "bbbb"]);
+}
+
+#[test]
+fn exec_pushes_status_and_output() {
+    let stub = Stub::start(|_| (200, push("p", "exec").to_string()));
+    let home = home_with_token("token");
+
+    let output = pb(home.path())
+        .args(["--api-url", &stub.url, "exec", "--lines", "2", "--"])
+        .args([
+            "sh",
+            "-c",
+            "echo one; echo two; echo three; echo oops >&2; exit 3",
+        ])
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.code(), Some(3));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "one\ntwo\nthree\n");
+    let push = stub.requests()[0].json();
+    assert!(push["title"]
+        .as_str()
+        .unwrap()
+        .ends_with("failed (exit code 3)"));
+    let body = push["body"].as_str().unwrap();
+    assert!(body.starts_with("Status: exit code 3\nRuntime: "));
+    assert!(body.ends_with("stdout:\ntwo\nthree\n\nstderr:\noops"));
+    assert!(push["guid"].as_str().unwrap().starts_with("pb-exec-"));
+}
+
+#[test]
+fn exec_on_failure_skips_success() {
+    let stub = Stub::start(|_| (200, push("p", "exec").to_string()));
+    let home = home_with_token("token");
+
+    let output = pb(home.path())
+        .args(["--api-url", &stub.url, "exec", "--on-failure", "--", "true"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    let output = pb(home.path())
+        .args(["--api-url", &stub.url, "exec", "--min-duration", "60"])
+        .args(["--", "false"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+
+    assert!(stub.requests().is_empty());
+}
+
+#[test]
+fn exec_keeps_command_status_when_push_fails() {
+    let stub = Stub::start(|_| (500, json!({ "error": { "message": "down" } }).to_string()));
+    let home = home_with_token("token");
+
+    let output = pb(home.path())
+        .args(["--api-url", &stub.url, "exec", "--", "sh", "-c", "exit 4"])
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.code(), Some(4));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Failed to send push"));
+}
+
+fn files_stub(fail: &'static str) -> Stub {
+    Stub::start(move |request| match request.path.as_str() {
+        "/v2/upload-request" => {
+            let file_name = request.json()["file_name"].as_str().unwrap().to_owned();
+            let body = json!({
+                "file_name": file_name,
+                "file_type": "application/octet-stream",
+                "file_url": format!("https://files.example/{file_name}"),
+                "upload_url": format!("http://{}/upload/{file_name}", request.header("Host").unwrap()),
+            });
+            (200, body.to_string())
+        }
+        path if path.starts_with("/upload/") => match path.ends_with(fail) {
+            true => (400, String::new()),
+            false => (204, String::new()),
+        },
+        _ => {
+            let mut push = push("f", "file");
+            push["file_name"] = request.json()["file_name"].clone();
+            (200, push.to_string())
+        }
+    })
+}
+
+fn created_file_names(stub: &Stub) -> Vec<String> {
+    let mut names: Vec<String> = stub
+        .requests()
+        .iter()
+        .filter(|request| request.path == "/v2/pushes")
+        .map(|request| request.json()["file_name"].as_str().unwrap().to_owned())
+        .collect();
+    names.sort();
+    names
+}
+
+#[test]
+fn push_several_files_and_globs() {
+    let stub = files_stub("none");
+    let home = home_with_token("token");
+    for name in ["a.txt", "b.txt", "c.log"] {
+        fs::write(home.path().join(name), name).unwrap();
+    }
+
+    let output = pb(home.path())
+        .current_dir(home.path())
+        .args(["--api-url", &stub.url, "push", "create", "--type", "file"])
+        .args([
+            "--file-type",
+            "text/plain",
+            "--file-name",
+            "*.txt",
+            "--file-name",
+            "c.log",
+        ])
+        .output()
+        .unwrap();
+
+    assert_eq!(stdout_json(&output)["pushes"].as_array().unwrap().len(), 3);
+    assert_eq!(created_file_names(&stub), ["a.txt", "b.txt", "c.log"]);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Sent 3 files"));
+}
+
+#[test]
+fn push_directory_and_report_failures() {
+    let stub = files_stub("bad.txt");
+    let home = home_with_token("token");
+    let dir = home.path().join("reports");
+    fs::create_dir_all(dir.join("nested")).unwrap();
+    fs::write(dir.join("good.txt"), "good").unwrap();
+    fs::write(dir.join("nested/bad.txt"), "bad").unwrap();
+
+    let output = pb(home.path())
+        .args(["--api-url", &stub.url, "push", "create", "--jobs", "2"])
+        .args(["--file-type", "text/plain", "--dir"])
+        .arg(&dir)
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.code(), Some(10));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("bad.txt"));
+    assert!(stderr.contains("1 of 2 files failed, 1 sent: "));
+    assert!(stderr.contains("good.txt (f)"));
+    assert_eq!(created_file_names(&stub), ["good.txt"]);
+}
+
+#[test]
+fn push_directory_as_zip() {
+    let stub = files_stub("none");
+    let home = home_with_token("token");
+    let dir = home.path().join("reports");
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("one.txt"), "one").unwrap();
+    fs::write(dir.join("two.txt"), "two").unwrap();
+
+    let output = pb(home.path())
+        .args(["--api-url", &stub.url, "push", "create", "--zip"])
+        .args(["--file-type", "application/zip", "--dir"])
+        .arg(&dir)
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(created_file_names(&stub), ["reports.zip"]);
+    let upload = stub
+        .requests()
+        .into_iter()
+        .find(|request| request.path == "/upload/reports.zip")
+        .unwrap();
+    assert!(String::from_utf8_lossy(&upload.body).contains("one.txt"));
+}
+
+#[test]
+fn push_files_with_detected_and_overridden_types() {
+    let stub = files_stub("none");
+    let home = home_with_token("token");
+    let dir = home.path().join("files");
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("image.png"), b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR").unwrap();
+    fs::write(dir.join("notes"), "plain text").unwrap();
+    fs::write(dir.join("trace.dat"), "plain text too").unwrap();
+
+    let output = pb(home.path())
+        .args(["--api-url", &stub.url, "push", "create"])
+        .args(["--type-override", "*.dat=application/x-trace", "--dir"])
+        .arg(&dir)
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    let mut types: Vec<(String, String)> = stub
+        .requests()
+        .iter()
+        .filter(|request| request.path == "/v2/upload-request")
+        .map(|request| {
+            let body = request.json();
+            (
+                body["file_name"].as_str().unwrap().to_owned(),
+                body["file_type"].as_str().unwrap().to_owned(),
+            )
+        })
+        .collect();
+    types.sort();
+    assert_eq!(
+        types,
+        [
+            (String::from("image.png"), String::from("image/png")),
+            (String::from("notes"), String::from("text/plain")),
+            (
+                String::from("trace.dat"),
+                String::from("application/x-trace")
+            ),
+        ]
+    );
+}
+
+fn file_push(iden: &str, file_name: &str, created: f64, host: &str) -> serde_json::Value {
+    let mut push = push(iden, "");
+    push["type"] = json!("file");
+    push["created"] = json!(created);
+    push["file_name"] = json!(file_name);
+    push["file_url"] = json!(format!("http://{host}/files/{file_name}"));
+    push
+}
+
+fn downloads_stub() -> Stub {
+    Stub::start(|request| {
+        let host = request.header("Host").unwrap().to_owned();
+        match request.path.as_str() {
+            "/v2/pushes" => {
+                let body = json!({ "pushes": [
+                    file_push("f2", "new.png", 1718000500.0, &host),
+                    push("n1", "note"),
+                    file_push("f1", "shot.png", 1718000000.0, &host),
+                    file_push("f0", "old.png", 1700000000.0, &host),
+                ]});
+                (200, body.to_string())
+            }
+            "/files/old.png" => (500, String::new()),
+            path => match path.strip_prefix("/files/") {
+                Some(name) => (200, format!("contents of {name}")),
+                None => (404, String::new()),
+            },
+        }
+    })
+}
+
+#[test]
+fn push_download_keeps_existing_files() {
+    let stub = downloads_stub();
+    let home = home_with_token("token");
+    let inbox = home.path().join("inbox");
+    fs::create_dir_all(&inbox).unwrap();
+    fs::write(inbox.join("shot.png"), "mine").unwrap();
+
+    let output = pb(home.path())
+        .args([
+            "--api-url",
+            &stub.url,
+            "push",
+            "download",
+            "f1",
+            "--verify-size",
+        ])
+        .arg("--dir")
+        .arg(&inbox)
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    let file = &stdout_json(&output)["files"][0];
+    assert_eq!(file["file_name"], "shot.png");
+    assert!(file["path"].as_str().unwrap().ends_with("shot (1).png"));
+    assert_eq!(fs::read_to_string(inbox.join("shot.png")).unwrap(), "mine");
+    assert_eq!(
+        fs::read_to_string(inbox.join("shot (1).png")).unwrap(),
+        "contents of shot.png"
+    );
+    let file_request = stub
+        .requests()
+        .into_iter()
+        .find(|request| request.path == "/files/shot.png")
+        .unwrap();
+    assert_eq!(file_request.header("Access-Token"), None);
+}
+
+#[test]
+fn push_download_all_since() {
+    let stub = downloads_stub();
+    let home = home_with_token("token");
+    let inbox = home.path().join("inbox");
+
+    let output = pb(home.path())
+        .args(["--api-url", &stub.url, "push", "download", "--all"])
+        .args(["--since", "2024-06-10T06:00:00Z", "--dir"])
+        .arg(&inbox)
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    let mut names: Vec<String> = fs::read_dir(&inbox)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+        .collect();
+    names.sort();
+    assert_eq!(names, ["new.png", "shot.png"]);
+    let list = &stub.requests()[0];
+    assert_eq!(
+        list.query_param("modified_after").as_deref(),
+        Some("1717999200")
+    );
+    assert_eq!(list.query_param("active").as_deref(), Some("true"));
+}
+
+#[test]
+fn push_download_all_names_downloaded_files_on_failure() {
+    let stub = downloads_stub();
+    let home = home_with_token("token");
+    let inbox = home.path().join("inbox");
+
+    let output = pb(home.path())
+        .args(["--api-url", &stub.url, "push", "download", "--all", "--dir"])
+        .arg(&inbox)
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.code(), Some(12));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("1 of 3 files failed, 2 downloaded: "));
+    assert!(stderr.contains("new.png") && stderr.contains("shot.png"));
+}
+
+fn search_stub() -> Stub {
+    Stub::start(|request| {
+        let host = request.header("Host").unwrap().to_owned();
+        let mut report = push("n1", "Weekly report");
+        report["body"] = json!("Build 42 passed");
+        report["sender_email"] = json!("Ana@Example.com");
+        report["created"] = json!(1718000300.0);
+        let mut dismissed = push("n2", "Old news");
+        dismissed["dismissed"] = json!(true);
+        let body = match request.query_param("cursor").as_deref() {
+            None => json!({
+                "pushes": [report, file_push("f1", "report.pdf", 1718000200.0, &host)],
+                "cursor": "page2",
+            }),
+            _ => json!({ "pushes": [dismissed, file_push("f0", "old.pdf", 1600000000.0, &host)] }),
+        };
+        (200, body.to_string())
+    })
+}
+
+fn search(stub: &Stub, args: &[&str]) -> Vec<String> {
+    let home = home_with_token("token");
+    let output = pb(home.path())
+        .args(["--api-url", &stub.url, "push", "search"])
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{output:?}");
+    stdout_json(&output)["pushes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|push| push["iden"].as_str().unwrap().to_owned())
+        .collect()
+}
+
+#[test]
+fn push_search_filters_history() {
+    let stub = search_stub();
+
+    assert_eq!(search(&stub, &["--text", "REPORT"]), ["n1", "f1"]);
+    assert_eq!(search(&stub, &["--regex", r"Build \d+"]), ["n1"]);
+    assert_eq!(
+        search(&stub, &["--has-file", "true", "--since", "2024-01-01"]),
+        ["f1"]
+    );
+    assert_eq!(search(&stub, &["--until", "2024-01-01"]), ["f0"]);
+    assert_eq!(search(&stub, &["--sender", "ana@example.com"]), ["n1"]);
+    assert_eq!(search(&stub, &["--dismissed", "true"]), ["n2"]);
+    assert_eq!(
+        search(&stub, &["--type", "file", "--max-items", "1"]),
+        ["f1"]
+    );
+
+    let since = stub
+        .requests()
+        .into_iter()
+        .filter_map(|request| request.query_param("modified_after"))
+        .next();
+    assert_eq!(since.as_deref(), Some("1704067200"));
+}
+
+#[test]
+fn push_search_rejects_invalid_regex() {
+    let home = home_with_token("token");
+    let output = pb(home.path())
+        .args(["push", "search", "--regex", "("])
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.code(), Some(2));
+}
+
+fn cache_stub() -> Stub {
+    Stub::start(|request| {
+        let mut first = push("p1", "first");
+        first["modified"] = json!(100.0);
+        let mut second = push("p2", "second");
+        second["modified"] = json!(200.0);
+        let body = match (request.path.as_str(), request.query_param("modified_after")) {
+            ("/v2/pushes", None) => json!({ "pushes": [second, first] }),
+            ("/v2/pushes", Some(_)) => {
+                let mut third = push("p3", "third");
+                third["modified"] = json!(300.0);
+                second["active"] = json!(false);
+                second["modified"] = json!(250.0);
+                json!({ "pushes": [third, second] })
+            }
+            (path, _) => json!({ path.trim_start_matches("/v2/"): [] }),
+        };
+        (200, body.to_string())
+    })
+}
+
+#[test]
+fn cache_syncs_incrementally_and_serves_offline() {
+    let stub = cache_stub();
+    let home = home_with_token("token");
+
+    let offline = pb(home.path())
+        .args(["--offline", "push", "list"])
+        .output()
+        .unwrap();
+    assert_eq!(offline.status.code(), Some(12));
+
+    for updated in [2, 1] {
+        let output = pb(home.path())
+            .args(["--api-url", &stub.url, "cache", "sync"])
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        assert_eq!(stdout_json(&output)["pushes"]["updated"], updated);
+    }
+    let pushes: Vec<_> = stub
+        .requests()
+        .into_iter()
+        .filter(|request| request.path == "/v2/pushes")
+        .collect();
+    assert_eq!(pushes[0].query_param("active").as_deref(), Some("true"));
+    assert_eq!(
+        pushes[1].query_param("modified_after").as_deref(),
+        Some("200")
+    );
+    assert_eq!(pushes[1].query_param("active"), None);
+
+    let output = pb(home.path())
+        .args(["--offline", "push", "list"])
+        .output()
+        .unwrap();
+    let idens: Vec<_> = stdout_json(&output)["pushes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|push| push["iden"].as_str().unwrap().to_owned())
+        .collect();
+    assert_eq!(idens, ["p3", "p1"]);
+
+    let requests = stub.requests().len();
+    let output = pb(home.path())
+        .args(["--offline", "push", "search", "--text", "third"])
+        .output()
+        .unwrap();
+    assert_eq!(stdout_json(&output)["pushes"][0]["iden"], "p3");
+    assert_eq!(stub.requests().len(), requests);
+}
+
+fn bulk_stub() -> Stub {
+    Stub::start(|request| match request.method.as_str() {
+        "GET" => {
+            let mut old_link = push("l1", "old link");
+            old_link["type"] = json!("link");
+            old_link["created"] = json!(1600000000.0);
+            let mut new_link = push("l2", "new link");
+            new_link["type"] = json!("link");
+            let mut dismissed = push("n2", "seen");
+            dismissed["dismissed"] = json!(true);
+            let body = json!({ "pushes": [new_link, push("n1", "note"), dismissed, old_link] });
+            (200, body.to_string())
+        }
+        "DELETE" => (200, String::from("{}")),
+        _ => (200, push("x", "updated").to_string()),
+    })
+}
+
+fn changed(stub: &Stub, method: &str) -> Vec<String> {
+    stub.requests()
+        .into_iter()
+        .filter(|request| request.method == method)
+        .map(|request| request.path)
+        .collect()
+}
+
+#[test]
+fn push_delete_by_filter_with_dry_run() {
+    let stub = bulk_stub();
+    let home = home_with_token("token");
+    let args = ["--api-url", &stub.url, "push", "delete"];
+    let filters = ["--older-than", "2024-01-01", "--type", "link"];
+
+    let output = pb(home.path())
+        .args(args)
+        .args(filters)
+        .arg("--dry-run")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(stdout_json(&output)["pushes"][0]["iden"], "l1");
+    assert!(changed(&stub, "DELETE").is_empty());
+
+    let output = pb(home.path())
+        .args(args)
+        .args(filters)
+        .arg("--yes")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(changed(&stub, "DELETE"), ["/v2/pushes/l1"]);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Deleted push \"old link\" (l1)"));
+}
+
+#[test]
+fn push_dismiss_by_iden_looks_through_the_list() {
+    let stub = bulk_stub();
+    let home = home_with_token("token");
+    let dismiss = |idens: &[&str]| {
+        pb(home.path())
+            .args(["--api-url", &stub.url, "push", "dismiss"])
+            .args(idens)
+            .output()
+            .unwrap()
+    };
+
+    assert!(dismiss(&["l1", "n1"]).status.success());
+    assert_eq!(dismiss(&["missing"]).status.code(), Some(6));
+
+    assert_eq!(changed(&stub, "POST"), ["/v2/pushes/l1", "/v2/pushes/n1"]);
+    assert!(stub
+        .requests()
+        .iter()
+        .filter(|request| request.method == "GET")
+        .all(|request| request.path == "/v2/pushes"));
+}
+
+#[test]
+fn push_dismiss_all_undismissed() {
+    let stub = bulk_stub();
+    let home = home_with_token("token");
+
+    let output = pb(home.path())
+        .args([
+            "--api-url",
+            &stub.url,
+            "push",
+            "dismiss",
+            "--all-undismissed",
+        ])
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(
+        changed(&stub, "POST"),
+        ["/v2/pushes/l2", "/v2/pushes/n1", "/v2/pushes/l1"]
+    );
+    let update = stub.requests().pop().unwrap();
+    assert_eq!(update.json(), json!({ "dismissed": true }));
+}
+
+#[test]
+fn push_delete_needs_idens_or_filters() {
+    let stub = bulk_stub();
+    let home = home_with_token("token");
+
+    let output = pb(home.path())
+        .args(["--api-url", &stub.url, "push", "delete"])
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.code(), Some(3));
+    assert!(stub.requests().is_empty());
+}
+
+#[test]
+fn push_dismiss_needs_idens_or_filters() {
+    let stub = bulk_stub();
+    let home = home_with_token("token");
+
+    let output = pb(home.path())
+        .args(["--api-url", &stub.url, "push", "dismiss"])
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.code(), Some(3));
+    assert!(changed(&stub, "POST").is_empty());
+}
+
+fn device_stub() -> Stub {
+    Stub::start(|request| match request.method.as_str() {
+        "GET" => {
+            let devices = json!({ "devices": [
+                { "iden": "d1", "active": true, "created": 1.0, "modified": 1.0, "nickname": "Phone", "model": "Pixel" },
+            ]});
+            (200, devices.to_string())
+        }
+        "DELETE" => (200, String::from("{}")),
+        _ => {
+            let mut device = request.json();
+            device["iden"] = json!("d2");
+            device["active"] = json!(true);
+            device["created"] = json!(2.0);
+            device["modified"] = json!(2.0);
+            (200, device.to_string())
+        }
+    })
+}
+
+#[test]
+fn delete_needs_confirmation_without_terminal() {
+    let stub = device_stub();
+    let home = home_with_token("token");
+
+    let output = pb(home.path())
+        .args(["--api-url", &stub.url, "device", "delete", "d1"])
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.code(), Some(3));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Delete device \"Phone\" (d1)"));
+    assert!(stub
+        .requests()
+        .iter()
+        .all(|request| request.method == "GET"));
+}
+
+#[test]
+fn delete_dry_run_prints_request() {
+    let stub = device_stub();
+    let home = home_with_token("token");
+
+    let output = pb(home.path())
+        .args([
+            "--api-url",
+            &stub.url,
+            "device",
+            "delete",
+            "d1",
+            "--dry-run",
+        ])
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    let json = stdout_json(&output);
+    assert_eq!(json["devices"][0]["nickname"], "Phone");
+    assert_eq!(json["requests"][0]["method"], "DELETE");
+    assert_eq!(
+        json["requests"][0]["url"],
+        format!("{}/devices/d1", stub.url)
+    );
+    assert!(stub
+        .requests()
+        .iter()
+        .all(|request| request.method == "GET"));
+}
+
+#[test]
+fn delete_all_lists_at_most_max_items() {
+    let stub = Stub::start(|request| {
+        let body = match request.query_param("cursor").as_deref() {
+            None => json!({ "pushes": [push("a", "1"), push("b", "2")], "cursor": "next" }),
+            _ => json!({ "pushes": [push("c", "3")] }),
+        };
+        (200, body.to_string())
+    });
+    let home = home_with_token("token");
+
+    let output = pb(home.path())
+        .args(["--api-url", &stub.url, "push", "delete-all"])
+        .args(["--max-items", "2", "--dry-run"])
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(stdout_json(&output)["pushes"].as_array().unwrap().len(), 2);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Only the first 2 pushes"));
+    assert_eq!(stub.requests().len(), 1);
+}
+
+#[test]
+fn delete_all_without_pushes_asks_nothing() {
+    let stub = Stub::start(|_| (200, json!({ "pushes": [] }).to_string()));
+    let home = home_with_token("token");
+
+    let output = pb(home.path())
+        .args(["--api-url", &stub.url, "push", "delete-all"])
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("No pushes to delete"));
+    assert!(stub
+        .requests()
+        .iter()
+        .all(|request| request.method == "GET"));
+}
+
+#[test]
+fn undo_creates_deleted_device_again() {
+    let stub = device_stub();
+    let home = home_with_token("token");
+
+    let output = pb(home.path())
+        .args(["--api-url", &stub.url, "device", "delete", "d1", "--yes"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(changed(&stub, "DELETE"), ["/v2/devices/d1"]);
+
+    let output = pb(home.path())
+        .args(["--api-url", &stub.url, "undo"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(stdout_json(&output)["created"][0]["nickname"], "Phone");
+    let create = stub.requests().pop().unwrap();
+    assert_eq!(create.path, "/v2/devices");
+    assert_eq!(create.json()["model"], "Pixel");
+
+    let output = pb(home.path())
+        .args(["--api-url", &stub.url, "undo"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(3));
+}
+
+#[test]
+fn undo_skips_deleted_texts() {
+    let stub = device_stub();
+    let home = home_with_token("token");
+    for args in [["device", "delete", "d1"], ["text", "delete", "txt1"]] {
+        let output = pb(home.path())
+            .args(["--api-url", &stub.url])
+            .args(args)
+            .arg("--yes")
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+    }
+
+    let output = pb(home.path())
+        .args(["--api-url", &stub.url, "undo"])
+        .output()
+        .unwrap();
+
+    assert_eq!(stdout_json(&output)["created"][0]["nickname"], "Phone");
+    let output = pb(home.path()).args(["undo", "--list"]).output().unwrap();
+    let deleted = stdout_json(&output)["deleted"].clone();
+    assert_eq!(deleted.as_array().unwrap().len(), 1);
+    assert_eq!(deleted[0]["kind"], "device");
+}
+
+#[test]
+fn names_resolve_to_idens() {
+    let stub = Stub::start(|request| match names(request) {
+        Some(response) => response,
+        None => (200, push("p", "hi").to_string()),
+    });
+    let home = home_with_token("token");
+    let create = |target: &str| {
+        pb(home.path())
+            .args(["--api-url", &stub.url, "push", "create", "--title", "hi"])
+            .args(["--device-iden", target])
+            .output()
+            .unwrap()
+    };
+
+    assert!(create("pixel 8").status.success());
+    assert!(create("ujpixel7").status.success());
+    assert!(create("ana@example.com").status.success());
+    let ambiguous = create("ujpix");
+    assert_eq!(ambiguous.status.code(), Some(3));
+    let stderr = String::from_utf8_lossy(&ambiguous.stderr);
+    assert!(stderr.contains("Pixel 7 (ujpixel7)") && stderr.contains("Pixel 8 (ujpixel8)"));
+
+    let created: Vec<_> = stub
+        .requests()
+        .into_iter()
+        .filter(|request| request.method == "POST")
+        .map(|request| request.json())
+        .collect();
+    assert_eq!(created[0]["device_iden"], "ujpixel8");
+    assert_eq!(created[1]["device_iden"], "ujpixel7");
+    assert_eq!(created[2]["device_iden"], Value::Null);
+    assert_eq!(created[2]["email"], "ana@example.com");
+    // Listed once, the listed iden and the email being used as they are and the prefix read from the names cache.
+    let listed: Vec<_> = stub
+        .requests()
+        .into_iter()
+        .filter(|request| request.method == "GET")
+        .map(|request| request.path)
+        .collect();
+    assert_eq!(listed, ["/v2/devices"]);
+}
+
+#[test]
+fn full_idens_and_emails_are_not_looked_up() {
+    let stub = Stub::start(|request| match request.method.as_str() {
+        "GET" => (500, json!({ "error": { "message": "down" } }).to_string()),
+        _ => (200, push("p", "hi").to_string()),
+    });
+    let home = home_with_token("token");
+    let create = |target: &str| {
+        pb(home.path())
+            .args(["--api-url", &stub.url, "push", "create", "--title", "hi"])
+            .args(["--device-iden", target])
+            .output()
+            .unwrap()
+    };
+
+    assert!(create("ujpah72o0sjAoRtnM0jc").status.success());
+    assert!(create("bob@example.com").status.success());
+
+    let requests = stub.requests();
+    assert!(requests.iter().all(|request| request.method == "POST"));
+    assert_eq!(requests[0].json()["device_iden"], "ujpah72o0sjAoRtnM0jc");
+    assert_eq!(requests[1].json()["device_iden"], Value::Null);
+    assert_eq!(requests[1].json()["email"], "bob@example.com");
+}
+
+#[test]
+fn channel_tag_resolves_to_channel_target() {
+    let stub = Stub::start(|request| match names(request) {
+        Some(response) => response,
+        None => (200, push("p", "hi").to_string()),
+    });
+    let home = home_with_token("token");
+
+    let output = pb(home.path())
+        .args(["--api-url", &stub.url, "send", "--device-iden", "Releases"])
+        .arg("hello")
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    let requests = stub.requests();
+    let created = requests
+        .iter()
+        .find(|request| request.method == "POST")
+        .unwrap()
+        .json();
+    assert_eq!(created["device_iden"], Value::Null);
+    assert_eq!(created["channel_tag"], "releases");
+    // Every kind listed once, the cached ones looked through before listing again.
+    let listed: Vec<_> = requests
+        .iter()
+        .filter(|request| request.method == "GET")
+        .map(|request| request.path.as_str())
+        .collect();
+    assert_eq!(listed, ["/v2/devices", "/v2/chats", "/v2/subscriptions"]);
+}
+
+#[test]
+fn device_register_reuses_device_with_same_nickname() {
+    let stub = device_stub();
+    let home = home_with_token("token");
+    let register = |nickname: &str| {
+        let output = pb(home.path())
+            .args(["--api-url", &stub.url, "device", "register"])
+            .args(["--nickname", nickname])
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        serde_json::from_slice::<Value>(&output.stdout).unwrap()
+    };
+    let source_device = || {
+        let output = pb(home.path())
+            .args(["config", "get", "source_device"])
+            .output()
+            .unwrap();
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    assert_eq!(register("build-server")["iden"], "d2");
+    assert_eq!(source_device(), "d2\n");
+    assert_eq!(register("Phone")["iden"], "d1");
+    assert_eq!(source_device(), "d1\n");
+
+    let created: Vec<_> = stub
+        .requests()
+        .into_iter()
+        .filter(|request| request.method == "POST")
+        .map(|request| request.json())
+        .collect();
+    assert_eq!(created.len(), 1);
+    assert_eq!(created[0]["nickname"], "build-server");
+    assert_eq!(created[0]["icon"], "system");
+}
+
+#[test]
+fn pushes_are_sent_from_registered_device() {
+    let stub = Stub::start(|request| match names(request) {
+        Some(response) => response,
+        None => (200, push("p", "hi").to_string()),
+    });
+    let home = home_with_token("token");
+    let output = pb(home.path())
+        .args(["config", "set", "source_device", "ujphone"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let output = pb(home.path())
+        .args(["--api-url", &stub.url, "push", "create", "--title", "hi"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    send(home.path(), &stub, &["hi"]);
+    pipe(home.path(), &stub, &["pipe"], "hi\n");
+    let output = pb(home.path())
+        .args(["--api-url", &stub.url, "exec", "--", "true"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let created: Vec<_> = stub
+        .requests()
+        .into_iter()
+        .filter(|request| request.method == "POST")
+        .map(|request| request.json())
+        .collect();
+    assert_eq!(created.len(), 4);
+    assert!(created
+        .iter()
+        .all(|push| push["source_device_iden"] == "ujphone"));
+}
+
+#[test]
+fn cache_sync_fails_offline() {
+    let home = home_with_token("token");
+
+    let output = pb(home.path())
+        .args(["--offline", "cache", "sync"])
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.code(), Some(3));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("does not work with --offline"));
+}
+
+fn daemon_stub() -> Stub {
+    Stub::start(|request| {
+        let host = request.header("Host").unwrap().to_owned();
+        match (request.method.as_str(), request.path.as_str()) {
+            ("GET", "/v2/pushes") => {
+                let pushes = match request.query_param("modified_after").as_deref() {
+                    None => {
+                        let mut old = push("p0", "old");
+                        old["modified"] = json!(100.0);
+                        vec![old]
+                    }
+                    Some("100") => {
+                        let mut dismissed = push("p1", "deploy before");
+                        dismissed["created"] = json!(50.0);
+                        dismissed["modified"] = json!(300.0);
+                        let mut file = file_push("f3", "report.txt", 250.0, &host);
+                        file["modified"] = json!(250.0);
+                        let mut note = push("p2", "deploy now");
+                        note["created"] = json!(200.0);
+                        note["modified"] = json!(200.0);
+                        note["sender_email"] = json!("ana@example.com");
+                        vec![dismissed, file, note]
+                    }
+                    Some(_) => vec![],
+                };
+                (200, json!({ "pushes": pushes }).to_string())
+            }
+            ("POST", "/v2/pushes/p2") => (200, push("p2", "deploy now").to_string()),
+            (_, path) => match path.strip_prefix("/files/") {
+                Some(name) => (200, format!("contents of {name}")),
+                None => (404, String::new()),
+            },
+        }
+    })
+}
+
+#[test]
+fn daemon_runs_rule_actions_on_new_pushes() {
+    let stub = daemon_stub();
+    let home = home_with_token("token");
+    fs::write(
+        home.path().join("rules.toml"),
+        r#"
+[[rule]]
+name = "deploy"
+title = "^deploy"
+sender = "ana@example.com"
+command = "echo \"$PB_PUSH_TITLE $PB_PUSH_SENDER_EMAIL\" >> ran.txt"
+dismiss = true
+
+[[rule]]
+type = "file"
+save_to = "inbox"
+clipboard = true
+"#,
+    )
+    .unwrap();
+    let daemon = || {
+        pb(home.path())
+            .current_dir(home.path())
+            .args(["--api-url", &stub.url, "daemon", "--once"])
+            .args([
+                "--rules",
+                "rules.toml",
+                "--clipboard-command",
+                "cat > clip.txt",
+            ])
+            .output()
+            .unwrap()
+    };
+
+    let output = daemon();
+    assert!(output.status.success(), "{output:?}");
+    let lines: Vec<Value> = String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0]["iden"], "p2");
+    assert_eq!(lines[0]["actions"], json!(["command", "dismiss"]));
+    assert_eq!(lines[1]["iden"], "f3");
+    assert_eq!(lines[1]["rule"], "2");
+    assert_eq!(lines[1]["actions"], json!(["save_to", "clipboard"]));
+    let read = |name: &str| fs::read_to_string(home.path().join(name)).unwrap();
+    assert_eq!(read("ran.txt"), "deploy now ana@example.com\n");
+    assert_eq!(read("inbox/report.txt"), "contents of report.txt");
+    assert!(read("clip.txt").ends_with("/files/report.txt"));
+    let dismissed = stub
+        .requests()
+        .into_iter()
+        .find(|request| request.method == "POST")
+        .unwrap();
+    assert_eq!(dismissed.json(), json!({ "dismissed": true }));
+
+    // Restarted, it goes on after the newest push seen instead of replaying them.
+    let output = daemon();
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+    let last = stub.requests().pop().unwrap();
+    assert_eq!(last.query_param("modified_after").as_deref(), Some("300"));
+    assert_eq!(read("ran.txt"), "deploy now ana@example.com\n");
+}
+
+#[test]
+fn daemon_rejects_invalid_rules() {
+    let home = home_with_token("token");
+    fs::write(home.path().join("rules.yaml"), "rule:\n  - title: \"(\"\n").unwrap();
+
+    let output = pb(home.path())
+        .current_dir(home.path())
+        .args(["--api-url", "http://127.0.0.1:9/v2", "daemon", "--once"])
+        .args(["--rules", "rules.yaml"])
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.code(), Some(3));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("rules.yaml"));
+}
+
+#[test]
+fn ephemeral_clip_and_dismiss() {
+    let stub = Stub::start(|request| match request.path.as_str() {
+        "/v2/users/me" => {
+            let user = json!({ "iden": "ujpah72o0", "created": 1.0, "modified": 1.0 });
+            (200, user.to_string())
+        }
+        "/v2/ephemerals" => (200, String::from("{}")),
+        _ => names(request).unwrap(),
+    });
+    let home = home_with_token("token");
+    let output = pb(home.path())
+        .args(["config", "set", "source_device", "ujphone"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let mut child = pb(home.path())
+        .args(["--api-url", &stub.url, "ephemeral", "clip"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(b"copied").unwrap();
+    assert!(child.wait_with_output().unwrap().status.success());
+    let output = pb(home.path())
+        .env("PB_ENCRYPTION_PASSWORD", "hunter2")
+        .args([
+            "--api-url",
+            &stub.url,
+            "ephemeral",
+            "clip",
+            "secret",
+            "--encrypt",
+        ])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let output = pb(home.path())
+        .args(["--api-url", &stub.url, "ephemeral", "dismiss"])
+        .args([
+            "--package-name",
+            "com.example.chat",
+            "--notification-id",
+            "7",
+        ])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let sent: Vec<Value> = stub
+        .requests()
+        .into_iter()
+        .filter(|request| request.path == "/v2/ephemerals")
+        .map(|request| request.json())
+        .collect();
+    assert_eq!(
+        sent[0],
+        json!({ "type": "push", "push": {
+            "type": "clip",
+            "body": "copied",
+            "source_user_iden": "ujpah72o0",
+            "source_device_iden": "ujphone",
+        }})
+    );
+    assert_eq!(sent[1]["push"]["encrypted"], true);
+    assert!(!sent[1].to_string().contains("secret"));
+    assert_eq!(
+        sent[2]["push"],
+        json!({
+            "type": "dismissal",
+            "package_name": "com.example.chat",
+            "notification_id": "7",
+            "source_user_iden": "ujpah72o0",
+        })
+    );
+}
+
+#[test]
+fn stream_prints_messages_until_closed() {
+    let stream = StreamStub::start(vec![
+        json!({ "type": "nop" }),
+        json!({ "type": "push", "push": { "type": "mirror", "title": "hi" } }),
+    ]);
+    let home = home_with_token("token");
+
+    let output = pb(home.path())
+        .args(["stream", "--url", &stream.url, "--no-reconnect"])
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.code(), Some(11));
+    let lines: Vec<Value> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(lines[0], json!({ "type": "nop" }));
+    assert_eq!(lines[1]["push"]["title"], "hi");
+    assert_eq!(stream.paths(), ["/websocket/token"]);
+}
+
+#[test]
+fn stream_prints_pushes_that_fail_to_decrypt() {
+    let stub = Stub::start(|_| {
+        let user = json!({ "iden": "ujpah72o0", "created": 1.0, "modified": 1.0 });
+        (200, user.to_string())
+    });
+    let stream = StreamStub::start(vec![
+        json!({ "type": "push", "push": { "encrypted": true, "ciphertext": "bm90IGVuY3J5cHRlZA==" } }),
+        json!({ "type": "nop" }),
+    ]);
+    let home = home_with_token("token");
+
+    let output = pb(home.path())
+        .env("PB_ENCRYPTION_PASSWORD", "hunter2")
+        .args([
+            "--api-url",
+            &stub.url,
+            "stream",
+            "--decrypt",
+            "--no-reconnect",
+        ])
+        .args(["--url", &stream.url])
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.code(), Some(11));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Failed to decrypt push"));
+    let lines: Vec<Value> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(lines[0]["push"]["encrypted"], true);
+    assert_eq!(lines[1], json!({ "type": "nop" }));
+}